@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use crate::actions::game_control::{Controls, GameControl};
+use crate::actions::{Actions, Aim, AimSource};
+use crate::combat::Health;
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+
+/// How the main attack picks where to shoot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AimMode {
+    /// Shoot at the cursor while the attack button is held
+    Manual,
+    /// Keep firing at the closest enemy in range
+    Nearest,
+    /// Keep firing at the enemy in range with the least health left
+    Weakest,
+}
+
+impl AimMode {
+    pub fn next(self) -> Self {
+        match self {
+            AimMode::Manual => AimMode::Nearest,
+            AimMode::Nearest => AimMode::Weakest,
+            AimMode::Weakest => AimMode::Manual,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AimSettings {
+    pub mode: AimMode,
+    /// Auto aim ignores enemies further away than this
+    pub range: f32,
+}
+
+impl Default for AimSettings {
+    fn default() -> Self {
+        Self {
+            // there's no mouse on a phone and touch is already used for movement
+            mode: if cfg!(any(target_os = "android", target_os = "ios")) {
+                AimMode::Nearest
+            } else {
+                AimMode::Manual
+            },
+            range: 800.0,
+        }
+    }
+}
+
+pub fn cycle_aim_mode(mut settings: ResMut<Settings>, controls: Controls) {
+    if controls.just_pressed(GameControl::CycleAimMode) {
+        settings.aim.mode = settings.aim.mode.next();
+        info!("aim mode: {:?}", settings.aim.mode);
+    }
+}
+
+pub fn auto_aim(
+    mut actions: ResMut<Actions>,
+    settings: Res<Settings>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(&Transform, &Health), (With<Enemy>, Without<Player>)>,
+) {
+    let settings = &settings.aim;
+    if settings.mode == AimMode::Manual {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let origin = player_transform.translation.truncate();
+
+    let in_range = enemy_query
+        .iter()
+        .map(|(transform, health)| {
+            let position = transform.translation.truncate();
+            (position, position.distance(origin), health.current)
+        })
+        .filter(|(_, distance, _)| *distance <= settings.range);
+
+    let target = match settings.mode {
+        AimMode::Manual => None,
+        AimMode::Nearest => in_range.min_by(|a, b| a.1.total_cmp(&b.1)),
+        AimMode::Weakest => in_range.min_by(|a, b| a.2.total_cmp(&b.2).then(a.1.total_cmp(&b.1))),
+    };
+    // auto aim holds fire for as long as there's something to shoot at
+    actions.fire.pressed = target.is_some();
+    if let Some((position, _, _)) = target {
+        actions.aim = Aim::new((position - origin).normalize_or_zero(), AimSource::Auto);
+    }
+}
@@ -1,49 +1,141 @@
-use bevy::prelude::{ButtonInput, KeyCode, MouseButton, Res};
+use crate::actions::input_map::{Binding, AXIS_DEADZONE, AXIS_THRESHOLD};
+use crate::settings::Settings;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum GameControl {
     Up,
     Down,
     Left,
     Right,
+    AimUp,
+    AimDown,
+    AimLeft,
+    AimRight,
     ZoomIn,
     ZoomOut,
     MainAttack,
+    CycleAimMode,
+    Pause,
 }
 
 impl GameControl {
-    pub fn pressed(
-        &self,
-        keyboard_input: &Res<ButtonInput<KeyCode>>,
-        mouse_input: &Res<ButtonInput<MouseButton>>,
-    ) -> bool {
+    pub const ALL: [GameControl; 13] = [
+        GameControl::Up,
+        GameControl::Down,
+        GameControl::Left,
+        GameControl::Right,
+        GameControl::AimUp,
+        GameControl::AimDown,
+        GameControl::AimLeft,
+        GameControl::AimRight,
+        GameControl::ZoomIn,
+        GameControl::ZoomOut,
+        GameControl::MainAttack,
+        GameControl::CycleAimMode,
+        GameControl::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
         match self {
-            GameControl::Up => {
-                keyboard_input.pressed(KeyCode::KeyW) || keyboard_input.pressed(KeyCode::ArrowUp)
-            }
-            GameControl::Down => {
-                keyboard_input.pressed(KeyCode::KeyS) || keyboard_input.pressed(KeyCode::ArrowDown)
-            }
-            GameControl::Left => {
-                keyboard_input.pressed(KeyCode::KeyA) || keyboard_input.pressed(KeyCode::ArrowLeft)
-            }
-            GameControl::Right => {
-                keyboard_input.pressed(KeyCode::KeyD) || keyboard_input.pressed(KeyCode::ArrowRight)
-            }
-            GameControl::ZoomIn => keyboard_input.pressed(KeyCode::KeyQ),
-            GameControl::ZoomOut => keyboard_input.pressed(KeyCode::KeyE),
-            GameControl::MainAttack => mouse_input.pressed(MouseButton::Left),
+            GameControl::Up => "Up",
+            GameControl::Down => "Down",
+            GameControl::Left => "Left",
+            GameControl::Right => "Right",
+            GameControl::AimUp => "Aim up",
+            GameControl::AimDown => "Aim down",
+            GameControl::AimLeft => "Aim left",
+            GameControl::AimRight => "Aim right",
+            GameControl::ZoomIn => "Zoom in",
+            GameControl::ZoomOut => "Zoom out",
+            GameControl::MainAttack => "Attack",
+            GameControl::CycleAimMode => "Aim mode",
+            GameControl::Pause => "Pause",
         }
     }
 }
 
-pub fn get_control_pressed(
-    control: GameControl,
-    input: &Res<ButtonInput<KeyCode>>,
-    mouse_input: &Res<ButtonInput<MouseButton>>,
-) -> f32 {
-    if control.pressed(input, mouse_input) {
-        1.0
-    } else {
-        0.0
+/// Reads the state of a [`GameControl`] through the player's input map
+#[derive(SystemParam)]
+pub struct Controls<'w, 's> {
+    settings: Res<'w, Settings>,
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    mouse_input: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    touches: Res<'w, Touches>,
+    window_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl<'w, 's> Controls<'w, 's> {
+    /// How strongly the control is held, 1 for buttons and the axis position past the deadzone for sticks
+    pub fn value(&self, control: GameControl) -> f32 {
+        self.settings
+            .input_map
+            .bindings(control)
+            .iter()
+            .map(|binding| self.binding_value(binding))
+            .fold(0.0, f32::max)
+    }
+
+    pub fn pressed(&self, control: GameControl) -> bool {
+        self.value(control) >= AXIS_THRESHOLD
+    }
+
+    /// Axes don't keep track of their previous position, so they never count as just pressed
+    pub fn just_pressed(&self, control: GameControl) -> bool {
+        self.settings
+            .input_map
+            .bindings(control)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => self.keyboard_input.just_pressed(*key),
+                Binding::Mouse(button) => self.mouse_input.just_pressed(*button),
+                Binding::GamepadButton(button) => self.gamepads.iter().any(|gamepad| {
+                    self.gamepad_buttons
+                        .just_pressed(GamepadButton::new(gamepad, *button))
+                }),
+                Binding::GamepadAxis { .. } => false,
+                Binding::TouchRegion { min, max } => self
+                    .touches
+                    .iter_just_pressed()
+                    .any(|touch| self.in_region(touch.position(), *min, *max)),
+            })
+    }
+
+    fn binding_value(&self, binding: &Binding) -> f32 {
+        let held = |pressed: bool| if pressed { 1.0 } else { 0.0 };
+        match binding {
+            Binding::Key(key) => held(self.keyboard_input.pressed(*key)),
+            Binding::Mouse(button) => held(self.mouse_input.pressed(*button)),
+            Binding::GamepadButton(button) => held(self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, *button))
+            })),
+            Binding::GamepadAxis { axis, positive } => self
+                .gamepads
+                .iter()
+                .filter_map(|gamepad| self.gamepad_axes.get(GamepadAxis::new(gamepad, *axis)))
+                .map(|value| if *positive { value } else { -value })
+                .map(|value| ((value - AXIS_DEADZONE) / (1.0 - AXIS_DEADZONE)).max(0.0))
+                .fold(0.0, f32::max),
+            Binding::TouchRegion { min, max } => held(
+                self.touches
+                    .iter()
+                    .any(|touch| self.in_region(touch.position(), *min, *max)),
+            ),
+        }
+    }
+
+    fn in_region(&self, position: Vec2, min: Vec2, max: Vec2) -> bool {
+        let Ok(window) = self.window_query.get_single() else {
+            return false;
+        };
+        let relative = position / Vec2::new(window.width(), window.height());
+        relative.cmpge(min).all() && relative.cmple(max).all()
     }
 }
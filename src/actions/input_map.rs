@@ -0,0 +1,201 @@
+use crate::actions::game_control::GameControl;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Axis positions closer to the center than this are ignored, so a worn stick doesn't drift
+pub const AXIS_DEADZONE: f32 = 0.15;
+/// How far an axis has to be pushed, past the deadzone, before it counts as pressed
+pub const AXIS_THRESHOLD: f32 = 0.5;
+
+/// A single physical input that can trigger a [`GameControl`]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButtonType),
+    /// Held as far as the axis is pushed in the given direction, pressed past [`AXIS_THRESHOLD`]
+    GamepadAxis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+    /// Pressed while a finger is inside this part of the screen, given as fractions of the window size
+    TouchRegion {
+        min: Vec2,
+        max: Vec2,
+    },
+}
+
+impl Binding {
+    /// Whether this binding comes from a gamepad, rebinding only replaces bindings of the same kind
+    pub fn is_gamepad(&self) -> bool {
+        matches!(
+            self,
+            Binding::GamepadButton(_) | Binding::GamepadAxis { .. }
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                for prefix in ["Key", "Digit", "Arrow"] {
+                    if let Some(short) = name.strip_prefix(prefix) {
+                        if prefix == "Arrow" {
+                            return format!("{short} arrow");
+                        }
+                        return short.to_string();
+                    }
+                }
+                name
+            }
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::GamepadButton(button) => format!("Pad {button:?}"),
+            Binding::GamepadAxis { axis, positive } => {
+                format!("Pad {axis:?}{}", if *positive { "+" } else { "-" })
+            }
+            Binding::TouchRegion { .. } => "Touch".to_string(),
+        }
+    }
+}
+
+/// Maps every control to the inputs that trigger it, saved with the settings
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct InputMap {
+    bindings: HashMap<GameControl, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::*;
+        let axis = |axis, positive| GamepadAxis { axis, positive };
+        Self {
+            bindings: HashMap::from_iter([
+                (
+                    GameControl::Up,
+                    vec![
+                        Key(KeyCode::KeyW),
+                        Key(KeyCode::ArrowUp),
+                        axis(GamepadAxisType::LeftStickY, true),
+                    ],
+                ),
+                (
+                    GameControl::Down,
+                    vec![
+                        Key(KeyCode::KeyS),
+                        Key(KeyCode::ArrowDown),
+                        axis(GamepadAxisType::LeftStickY, false),
+                    ],
+                ),
+                (
+                    GameControl::Left,
+                    vec![
+                        Key(KeyCode::KeyA),
+                        Key(KeyCode::ArrowLeft),
+                        axis(GamepadAxisType::LeftStickX, false),
+                    ],
+                ),
+                (
+                    GameControl::Right,
+                    vec![
+                        Key(KeyCode::KeyD),
+                        Key(KeyCode::ArrowRight),
+                        axis(GamepadAxisType::LeftStickX, true),
+                    ],
+                ),
+                (
+                    GameControl::AimUp,
+                    vec![axis(GamepadAxisType::RightStickY, true)],
+                ),
+                (
+                    GameControl::AimDown,
+                    vec![axis(GamepadAxisType::RightStickY, false)],
+                ),
+                (
+                    GameControl::AimLeft,
+                    vec![axis(GamepadAxisType::RightStickX, false)],
+                ),
+                (
+                    GameControl::AimRight,
+                    vec![axis(GamepadAxisType::RightStickX, true)],
+                ),
+                (
+                    GameControl::ZoomIn,
+                    vec![
+                        Key(KeyCode::KeyQ),
+                        GamepadButton(GamepadButtonType::LeftTrigger),
+                    ],
+                ),
+                (
+                    GameControl::ZoomOut,
+                    vec![
+                        Key(KeyCode::KeyE),
+                        GamepadButton(GamepadButtonType::RightTrigger),
+                    ],
+                ),
+                (
+                    GameControl::MainAttack,
+                    vec![
+                        Mouse(MouseButton::Left),
+                        GamepadButton(GamepadButtonType::RightTrigger2),
+                    ],
+                ),
+                (
+                    GameControl::CycleAimMode,
+                    vec![Key(KeyCode::KeyT), GamepadButton(GamepadButtonType::North)],
+                ),
+                (
+                    GameControl::Pause,
+                    vec![
+                        Key(KeyCode::Escape),
+                        GamepadButton(GamepadButtonType::Start),
+                    ],
+                ),
+            ]),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, control: GameControl) -> &[Binding] {
+        self.bindings.get(&control).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `binding` to `control`, replacing its bindings of the same kind (gamepad or not)
+    /// A control that had this binding before loses it, and is returned so the conflict can be shown
+    pub fn rebind(&mut self, control: GameControl, binding: Binding) -> Option<GameControl> {
+        let previous = self.control_for(binding).filter(|other| *other != control);
+        if let Some(other) = previous {
+            if let Some(bindings) = self.bindings.get_mut(&other) {
+                bindings.retain(|bound| *bound != binding);
+            }
+        }
+
+        let bindings = self.bindings.entry(control).or_default();
+        bindings.retain(|bound| bound.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+        previous
+    }
+
+    fn control_for(&self, binding: Binding) -> Option<GameControl> {
+        GameControl::ALL
+            .into_iter()
+            .find(|control| self.bindings(*control).contains(&binding))
+    }
+
+    /// Bindings used by more than one control, e.g. from a hand-edited settings file
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<GameControl>)> {
+        let mut conflicts: Vec<(Binding, Vec<GameControl>)> = Vec::new();
+        for control in GameControl::ALL {
+            for binding in self.bindings(control) {
+                match conflicts.iter_mut().find(|(bound, _)| bound == binding) {
+                    Some((_, controls)) => controls.push(control),
+                    None => conflicts.push((*binding, vec![control])),
+                }
+            }
+        }
+        conflicts.retain(|(_, controls)| controls.len() > 1);
+        conflicts
+    }
+}
@@ -1,39 +1,170 @@
+use bevy::input::mouse::MouseWheel;
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-use crate::actions::game_control::{get_control_pressed, GameControl};
+use crate::actions::aim::{auto_aim, cycle_aim_mode};
+use crate::actions::rebind::{capture_binding, click_rebind_buttons, update_binding_labels};
+use crate::actions::touch::{
+    apply_touch_controls, draw_virtual_sticks, update_virtual_sticks, VirtualSticks,
+};
+use crate::menu::MenuNavigation;
 use crate::player::Player;
+use crate::replay::not_replaying;
 use crate::GameState;
 use crate::GameplaySet::InputHandling;
 
+pub use crate::actions::aim::AimSettings;
+pub use crate::actions::game_control::{Controls, GameControl};
+pub use crate::actions::input_map::{InputMap, AXIS_THRESHOLD};
+pub use crate::actions::rebind::{not_rebinding, spawn_controls_section, Rebinding};
+pub use crate::actions::touch::{TouchScheme, TouchSettings};
+
+mod aim;
 mod game_control;
+mod input_map;
+mod rebind;
+mod touch;
 
 pub const FOLLOW_EPSILON: f32 = 5.;
 
 pub struct ActionsPlugin;
 
-// This plugin listens for keyboard input and converts the input into Actions
+// This plugin listens for keyboard, mouse, gamepad and touch input and converts the input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Actions>()
             .init_resource::<MouseCoords>()
+            .init_resource::<Rebinding>()
+            .init_resource::<VirtualSticks>()
+            // the controls are rebound from the settings panel, which is open outside of gameplay
+            .add_systems(
+                Update,
+                (click_rebind_buttons, capture_binding, update_binding_labels)
+                    .chain()
+                    .after(MenuNavigation),
+            )
+            // input is read every frame, gameplay picks up the latest actions on its next tick
             .add_systems(
                 Update,
-                (cursor_system, set_movement_actions)
+                (
+                    cursor_system,
+                    update_virtual_sticks,
+                    set_movement_actions,
+                    cycle_aim_mode,
+                    auto_aim,
+                    apply_touch_controls,
+                    set_weapon_actions,
+                )
                     .chain()
                     .run_if(in_state(GameState::Playing))
-                    .in_set(InputHandling),
+                    .run_if(not_replaying),
+            )
+            // replays feed recorded actions in before this, see `ReplayPlugin`
+            .add_systems(FixedUpdate, update_fire_state.in_set(InputHandling))
+            .add_systems(
+                Update,
+                draw_virtual_sticks.run_if(in_state(GameState::Playing)),
             );
     }
 }
 
-#[derive(Default, Resource)]
+#[derive(Default, Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Actions {
     pub player_movement: Option<Vec2>,
     pub camera_movement: Option<Vec3>,
-    pub shoot: Option<Vec2>,
+    pub aim: Aim,
+    pub fire: FireState,
+    /// Manual weapon picked with the number keys since the last tick
+    pub weapon_slot: Option<usize>,
+    /// Scroll steps through the manual weapons since the last tick
+    pub weapon_cycle: i32,
+}
+
+impl Actions {
+    /// Only the input part of these actions, the fire transitions are worked out again from `fire.pressed`
+    pub fn recorded(&self) -> Self {
+        Self {
+            fire: FireState {
+                pressed: self.fire.pressed,
+                ..default()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Takes over actions saved with [`Actions::recorded`], keeping track of how long fire has been held
+    pub fn replay(&mut self, recorded: &Actions) {
+        let fire = FireState {
+            pressed: recorded.fire.pressed,
+            ..self.fire
+        };
+        *self = recorded.clone();
+        self.fire = fire;
+    }
+}
+
+/// Where the input device last used for aiming points
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum AimSource {
+    #[default]
+    None,
+    Mouse,
+    Stick,
+    /// Picked by one of the auto aim modes, see [`AimSettings`]
+    Auto,
+    Touch,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Aim {
+    /// Normalized direction from the player, `None` while nothing is aiming
+    pub direction: Option<Vec2>,
+    pub source: AimSource,
+}
+
+impl Aim {
+    pub fn new(direction: Vec2, source: AimSource) -> Self {
+        Self {
+            direction: Some(direction),
+            source,
+        }
+    }
+}
+
+/// The main attack input over time, so weapons can fire on press, while held or on release
+#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FireState {
+    /// Fire is held this frame
+    pub pressed: bool,
+    #[serde(skip)]
+    pub just_pressed: bool,
+    #[serde(skip)]
+    pub just_released: bool,
+    /// How long fire has been held, still set on the frame it's released so charged attacks can read it
+    #[serde(skip)]
+    pub held: Duration,
+    #[serde(skip)]
+    was_pressed: bool,
+}
+
+impl FireState {
+    fn update(&mut self, delta: Duration) {
+        self.just_pressed = self.pressed && !self.was_pressed;
+        self.just_released = !self.pressed && self.was_pressed;
+        if self.just_pressed {
+            self.held = Duration::ZERO;
+        }
+        if self.pressed {
+            self.held += delta;
+        } else if !self.just_released {
+            self.held = Duration::ZERO;
+        }
+        self.was_pressed = self.pressed;
+    }
 }
 
 #[derive(Default, Resource)]
@@ -44,33 +175,21 @@ pub struct MouseCoords {
 
 pub fn set_movement_actions(
     mut actions: ResMut<Actions>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mouse_input: Res<ButtonInput<MouseButton>>,
-    touch_input: Res<Touches>,
+    controls: Controls,
     player: Query<&Transform, With<Player>>,
-    camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
     mouse_world_coords: Res<MouseCoords>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut last_stick_aim: Local<Option<Vec2>>,
 ) {
-    let mut player_movement = Vec2::new(
-        get_control_pressed(GameControl::Right, &keyboard_input, &mouse_input)
-            - get_control_pressed(GameControl::Left, &keyboard_input, &mouse_input),
-        get_control_pressed(GameControl::Up, &keyboard_input, &mouse_input)
-            - get_control_pressed(GameControl::Down, &keyboard_input, &mouse_input),
-    );
-
-    if let Some(touch_position) = touch_input.first_pressed_position() {
-        let (camera, camera_transform) = camera.single();
-        if let Some(touch_position) = camera.viewport_to_world_2d(camera_transform, touch_position)
-        {
-            let diff = touch_position - player.single().translation.xy();
-            if diff.length() > FOLLOW_EPSILON {
-                player_movement = diff.normalize();
-            }
-        }
-    }
+    // analog sticks move slower when only pushed slightly, keys always move at full speed
+    let player_movement = Vec2::new(
+        controls.value(GameControl::Right) - controls.value(GameControl::Left),
+        controls.value(GameControl::Up) - controls.value(GameControl::Down),
+    )
+    .clamp_length_max(1.0);
 
     if player_movement != Vec2::ZERO {
-        actions.player_movement = Some(player_movement.normalize());
+        actions.player_movement = Some(player_movement);
     } else {
         actions.player_movement = None;
     }
@@ -78,8 +197,7 @@ pub fn set_movement_actions(
     let camera_movement = Vec3::new(
         0.0,
         0.0,
-        get_control_pressed(GameControl::ZoomIn, &keyboard_input, &mouse_input)
-            - get_control_pressed(GameControl::ZoomOut, &keyboard_input, &mouse_input),
+        controls.value(GameControl::ZoomIn) - controls.value(GameControl::ZoomOut),
     );
 
     // touch position affect camera?
@@ -89,11 +207,69 @@ pub fn set_movement_actions(
         actions.camera_movement = None;
     }
 
-    // shoot action
-    if GameControl::MainAttack.pressed(&keyboard_input, &mouse_input) {
-        actions.shoot = Some(mouse_world_coords.world_coords.unwrap());
-    } else {
-        actions.shoot = None;
+    // twin-stick aiming fires while the stick is pushed, otherwise the aim follows the cursor
+    // or, once the mouse hasn't moved since, the last direction the stick pointed in
+    let stick_aim = Vec2::new(
+        controls.value(GameControl::AimRight) - controls.value(GameControl::AimLeft),
+        controls.value(GameControl::AimUp) - controls.value(GameControl::AimDown),
+    );
+    if cursor_moved.read().count() > 0 {
+        *last_stick_aim = None;
+    }
+    let stick_pushed = stick_aim.length() >= AXIS_THRESHOLD;
+    if stick_pushed {
+        *last_stick_aim = Some(stick_aim.normalize());
+    }
+    // the cursor has no world position while it's outside the window
+    let cursor_aim = mouse_world_coords
+        .world_coords
+        .zip(player.get_single().ok())
+        .map(|(cursor, transform)| (cursor - transform.translation.xy()).normalize_or_zero());
+
+    actions.aim = match (*last_stick_aim, cursor_aim) {
+        (Some(direction), _) => Aim::new(direction, AimSource::Stick),
+        (None, Some(direction)) => Aim::new(direction, AimSource::Mouse),
+        (None, None) => Aim::default(),
+    };
+    // auto aim and touch controls override these later when enabled
+    actions.fire.pressed = stick_pushed || controls.pressed(GameControl::MainAttack);
+}
+
+/// Works out the fire state transitions every tick, once every input had its say in `Actions::fire.pressed`
+pub fn update_fire_state(time: Res<Time>, mut actions: ResMut<Actions>) {
+    actions.fire.update(time.delta());
+}
+
+const WEAPON_SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+pub fn set_weapon_actions(
+    mut actions: ResMut<Actions>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+) {
+    // several frames can pass between ticks, so presses are kept until `switch_weapon` uses them up
+    if let Some(slot) = WEAPON_SLOT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+    {
+        actions.weapon_slot = Some(slot);
+    }
+
+    let scroll: f32 = mouse_wheel.read().map(|event| event.y).sum();
+    if scroll > 0.0 {
+        actions.weapon_cycle -= 1;
+    } else if scroll < 0.0 {
+        actions.weapon_cycle += 1;
     }
 }
 
@@ -109,5 +285,8 @@ pub fn cursor_system(
     if let Some(cursor_position) = window.cursor_position() {
         coords.logical_coords = Some(cursor_position);
         coords.world_coords = camera.viewport_to_world_2d(camera_transform, cursor_position);
+    } else {
+        coords.logical_coords = None;
+        coords.world_coords = None;
     }
 }
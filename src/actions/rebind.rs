@@ -0,0 +1,213 @@
+use crate::actions::game_control::GameControl;
+use crate::actions::input_map::{Binding, InputMap, AXIS_THRESHOLD};
+use crate::menu::{spawn_button, text_style};
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+/// Stick axes that can be bound, triggers are read as buttons
+const BINDABLE_AXES: [GamepadAxisType; 4] = [
+    GamepadAxisType::LeftStickX,
+    GamepadAxisType::LeftStickY,
+    GamepadAxisType::RightStickX,
+    GamepadAxisType::RightStickY,
+];
+
+/// The control waiting for its new binding after a rebind button was clicked
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    control: Option<GameControl>,
+    /// Explains the last rebind when it took a binding away from another control
+    message: Option<String>,
+}
+
+/// Run condition keeping other systems from reacting to the input that's being bound
+pub fn not_rebinding(rebinding: Res<Rebinding>) -> bool {
+    rebinding.control.is_none() && !rebinding.is_changed()
+}
+
+#[derive(Component)]
+pub struct RebindButton(GameControl);
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+#[derive(Component)]
+pub struct BindingLabel(GameControl);
+
+#[derive(Component)]
+pub struct RebindStatus;
+
+fn spawn_binding_row(parent: &mut ChildBuilder, control: GameControl) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                TextBundle::from_section("", text_style(20.0)).with_style(Style {
+                    width: Val::Px(300.0),
+                    ..default()
+                }),
+                BindingLabel(control),
+            ));
+            spawn_button(row, "Rebind", 120.0, RebindButton(control));
+        });
+}
+
+/// Lists every control with its bindings and a button to rebind it, in two columns of the settings panel
+pub fn spawn_controls_section(parent: &mut ChildBuilder) {
+    let column = NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        ..default()
+    };
+    // movement and aiming first, everything else next to it
+    let (movement, other) = GameControl::ALL.split_at(8);
+    parent.spawn(column.clone()).with_children(|column| {
+        for control in movement {
+            spawn_binding_row(column, *control);
+        }
+    });
+    parent.spawn(column).with_children(|column| {
+        for control in other {
+            spawn_binding_row(column, *control);
+        }
+        spawn_button(column, "Reset controls", 220.0, ResetBindingsButton);
+        column.spawn((TextBundle::from_section("", text_style(18.0)), RebindStatus));
+    });
+}
+
+pub fn click_rebind_buttons(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    interaction_query: Query<
+        (
+            &Interaction,
+            Option<&RebindButton>,
+            Has<ResetBindingsButton>,
+        ),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, rebind, reset) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(rebind) = rebind {
+            rebinding.control = Some(rebind.0);
+            rebinding.message = None;
+        } else if reset {
+            settings.input_map = InputMap::default();
+            rebinding.message = None;
+        }
+    }
+}
+
+/// Binds the next key, mouse button, gamepad button or stick direction to the control being rebound
+/// Escape cancels instead of being bound
+pub fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    // the click on the rebind button started this, it shouldn't become the binding
+    let Some(control) = rebinding.control.filter(|_| !rebinding.is_changed()) else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        rebinding.control = None;
+        return;
+    }
+
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::GamepadButton(button.button_type))
+        })
+        .or_else(|| {
+            gamepads.iter().find_map(|gamepad| {
+                BINDABLE_AXES.into_iter().find_map(|axis| {
+                    let value = gamepad_axes.get(GamepadAxis::new(gamepad, axis))?;
+                    (value.abs() > AXIS_THRESHOLD).then_some(Binding::GamepadAxis {
+                        axis,
+                        positive: value > 0.0,
+                    })
+                })
+            })
+        });
+    let Some(binding) = binding else {
+        return;
+    };
+
+    let previous = settings.input_map.rebind(control, binding);
+    rebinding.message = previous.map(|other| {
+        format!(
+            "{} moved from {} to {}",
+            binding.describe(),
+            other.name(),
+            control.name()
+        )
+    });
+    rebinding.control = None;
+}
+
+pub fn update_binding_labels(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut label_query: Query<(&mut Text, &BindingLabel), Without<RebindStatus>>,
+    mut status_query: Query<&mut Text, With<RebindStatus>>,
+) {
+    for (mut text, label) in label_query.iter_mut() {
+        text.sections[0].value = if rebinding.control == Some(label.0) {
+            format!("{}: press a key or button (Esc cancels)", label.0.name())
+        } else {
+            let bindings: Vec<String> = settings
+                .input_map
+                .bindings(label.0)
+                .iter()
+                .map(Binding::describe)
+                .collect();
+            format!("{}: {}", label.0.name(), bindings.join(", "))
+        };
+    }
+
+    let conflicts = settings.input_map.conflicts();
+    let status = rebinding.message.clone().unwrap_or_else(|| {
+        conflicts
+            .iter()
+            .map(|(binding, controls)| {
+                let names: Vec<&str> = controls.iter().map(GameControl::name).collect();
+                format!("{} is bound to {}", binding.describe(), names.join(" and "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    for mut text in status_query.iter_mut() {
+        text.sections[0].value.clone_from(&status);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::actions::{Actions, Aim, AimSource, FOLLOW_EPSILON};
+use crate::player::Player;
+use crate::settings::Settings;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+/// Stick positions closer to the center than this, as a fraction of the radius, are ignored
+const STICK_DEADZONE: f32 = 0.1;
+/// The right stick only fires once it's pushed this far
+const FIRE_THRESHOLD: f32 = 0.3;
+const STICK_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TouchScheme {
+    /// The player walks toward the finger, aiming is left to auto aim
+    FollowFinger,
+    /// A stick appears wherever a finger lands, on the left half to move and on the right half to aim and fire
+    TwinStick,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TouchSettings {
+    pub scheme: TouchScheme,
+    /// How far a finger has to move from where it landed to push a stick all the way, in logical pixels
+    pub stick_radius: f32,
+    pub stick_opacity: f32,
+}
+
+impl Default for TouchSettings {
+    fn default() -> Self {
+        Self {
+            scheme: TouchScheme::TwinStick,
+            stick_radius: 80.0,
+            stick_opacity: 0.4,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct VirtualStick {
+    touch: Option<u64>,
+    /// Where the finger landed, in window coordinates
+    origin: Vec2,
+    position: Vec2,
+}
+
+impl VirtualStick {
+    /// How far the stick is pushed, with up being positive y like in the world
+    fn value(&self, radius: f32) -> Vec2 {
+        if self.touch.is_none() {
+            return Vec2::ZERO;
+        }
+        let offset = (self.position - self.origin) / radius;
+        let value = Vec2::new(offset.x, -offset.y).clamp_length_max(1.0);
+        if value.length() < STICK_DEADZONE {
+            Vec2::ZERO
+        } else {
+            value
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct VirtualSticks {
+    pub left: VirtualStick,
+    pub right: VirtualStick,
+}
+
+/// Assigns new touches to the stick on their half of the screen and follows them until they lift
+pub fn update_virtual_sticks(
+    settings: Res<Settings>,
+    touch_input: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut sticks: ResMut<VirtualSticks>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let sticks = &mut *sticks;
+    for stick in [&mut sticks.left, &mut sticks.right] {
+        match stick.touch.and_then(|id| touch_input.get_pressed(id)) {
+            Some(touch) => stick.position = touch.position(),
+            None => stick.touch = None,
+        }
+    }
+    if settings.touch.scheme != TouchScheme::TwinStick {
+        return;
+    }
+
+    for touch in touch_input.iter_just_pressed() {
+        let stick = if touch.position().x < window.width() / 2.0 {
+            &mut sticks.left
+        } else {
+            &mut sticks.right
+        };
+        if stick.touch.is_none() {
+            *stick = VirtualStick {
+                touch: Some(touch.id()),
+                origin: touch.position(),
+                position: touch.position(),
+            };
+        }
+    }
+}
+
+/// Overrides the mouse, keyboard and gamepad actions while a finger is down
+pub fn apply_touch_controls(
+    mut actions: ResMut<Actions>,
+    settings: Res<Settings>,
+    sticks: Res<VirtualSticks>,
+    touch_input: Res<Touches>,
+    player: Query<&Transform, With<Player>>,
+    camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    match settings.touch.scheme {
+        TouchScheme::FollowFinger => {
+            let Some(touch_position) = touch_input.first_pressed_position() else {
+                return;
+            };
+            let (Ok((camera, camera_transform)), Ok(player_transform)) =
+                (camera.get_single(), player.get_single())
+            else {
+                return;
+            };
+            if let Some(touch_position) =
+                camera.viewport_to_world_2d(camera_transform, touch_position)
+            {
+                let diff = touch_position - player_transform.translation.xy();
+                if diff.length() > FOLLOW_EPSILON {
+                    actions.player_movement = Some(diff.normalize());
+                }
+            }
+        }
+        TouchScheme::TwinStick => {
+            let radius = settings.touch.stick_radius;
+            let movement = sticks.left.value(radius);
+            if movement != Vec2::ZERO {
+                actions.player_movement = Some(movement);
+            }
+            let aim = sticks.right.value(radius);
+            if aim.length() >= FIRE_THRESHOLD {
+                actions.aim = Aim::new(aim.normalize(), AimSource::Touch);
+                actions.fire.pressed = true;
+            }
+        }
+    }
+}
+
+/// Draws the sticks under the fingers, converted from window to world coordinates
+pub fn draw_virtual_sticks(
+    mut gizmos: Gizmos,
+    settings: Res<Settings>,
+    sticks: Res<VirtualSticks>,
+    camera: Query<(&Camera, &GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let Ok((camera, camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    let radius = settings.touch.stick_radius;
+    let color = STICK_COLOR.with_a(settings.touch.stick_opacity);
+    for stick in [&sticks.left, &sticks.right] {
+        if stick.touch.is_none() {
+            continue;
+        }
+        let knob = stick.origin + (stick.position - stick.origin).clamp_length_max(radius);
+        let (Some(origin), Some(knob)) = (
+            camera.viewport_to_world_2d(camera_transform, stick.origin),
+            camera.viewport_to_world_2d(camera_transform, knob),
+        ) else {
+            continue;
+        };
+        gizmos.circle_2d(origin, radius * projection.scale, color);
+        gizmos.circle_2d(knob, radius * 0.4 * projection.scale, color);
+    }
+}
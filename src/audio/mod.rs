@@ -0,0 +1,207 @@
+use crate::audio::music::{mix_music, start_music, stop_music, MusicLayers};
+use crate::audio::sfx::{play_sounds, sounds_from_gameplay, update_sound_emitters, Voices};
+use crate::loading::AudioAssets;
+use crate::movement::Velocity;
+use crate::player::Player;
+use crate::run::RunSetup;
+use crate::settings::Settings;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use std::time::Duration;
+
+pub use crate::audio::sfx::{Sfx, SoundEvent};
+
+mod music;
+mod sfx;
+
+/// Volume of the engine at full speed and thrust
+const FLYING_VOLUME: f32 = 0.3;
+/// The engine is as loud and high as speed alone makes it from this speed on
+const CRUISING_SPEED: f32 = 150.0;
+/// Changing velocity this fast, in units per second squared, is full thrust
+const FULL_THRUST: f32 = 600.0;
+/// How quickly the thrust follows the player's acceleration, so single frame jolts don't pop
+const THRUST_SMOOTHING: f32 = 6.0;
+const ENGINE_MIN_RATE: f32 = 0.8;
+const ENGINE_MAX_RATE: f32 = 1.2;
+const ENGINE_THRUST_RATE: f32 = 0.15;
+/// Below this volume the engine fades out and pauses
+const ENGINE_SILENT: f32 = 0.01;
+const ENGINE_FADE: Duration = Duration::from_millis(300);
+/// Volume and rate glide over this long, and only change once they moved more than `ENGINE_THRESHOLD`
+const ENGINE_TWEEN: Duration = Duration::from_millis(100);
+const ENGINE_THRESHOLD: f32 = 0.01;
+
+pub struct InternalAudioPlugin;
+
+// This plugin is responsible to control the game audio
+// Music and sound effects play on their own channels, so pausing the game can stop one and keep the other
+// Sound effects are requested through [`SoundEvent`]s and panned by where they happen relative to the camera
+// The volume settings are applied to the channels, volumes set on an instance are relative to its channel
+impl Plugin for InternalAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(AudioPlugin)
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_event::<SoundEvent>()
+            .init_resource::<Voices>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (start_audio, start_music).in_set(RunSetup),
+            )
+            .add_systems(OnEnter(GameState::GameOver), (stop_audio, stop_music))
+            .add_systems(OnEnter(GameState::Menu), (stop_audio, stop_music))
+            .add_systems(OnEnter(GameState::Paused), pause_sounds)
+            .add_systems(OnExit(GameState::Paused), resume_sounds)
+            .add_systems(Update, apply_volume.run_if(resource_changed::<Settings>))
+            .add_systems(
+                Update,
+                control_flying_sound.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (sounds_from_gameplay, play_sounds, update_sound_emitters)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                mix_music
+                    .run_if(resource_exists::<MusicLayers>)
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+            );
+    }
+}
+
+/// Channel for the music stems
+#[derive(Resource)]
+pub struct MusicChannel;
+
+/// Channel for sound effects, including the engine
+#[derive(Resource)]
+pub struct SfxChannel;
+
+/// The looping engine sound and what it was last set to
+#[derive(Resource)]
+struct FlyingAudio {
+    instance: Handle<AudioInstance>,
+    /// The player's velocity last frame
+    velocity: Vec2,
+    /// Smoothed acceleration, from 0 to 1
+    thrust: f32,
+    volume: f32,
+    rate: f32,
+}
+
+fn start_audio(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+) {
+    sfx.stop();
+    // starts silent and paused, `control_flying_sound` fades it in once the player moves
+    let instance = sfx
+        .play(audio_assets.flying.clone())
+        .looped()
+        .with_volume(0.0)
+        .with_playback_rate(ENGINE_MIN_RATE as f64)
+        .paused()
+        .handle();
+    commands.insert_resource(FlyingAudio {
+        instance,
+        velocity: Vec2::ZERO,
+        thrust: 0.0,
+        volume: 0.0,
+        rate: ENGINE_MIN_RATE,
+    });
+}
+
+fn stop_audio(sfx: Res<AudioChannel<SfxChannel>>) {
+    sfx.stop();
+}
+
+/// Music keeps playing while paused, quieter, see `mix_music`
+fn pause_sounds(sfx: Res<AudioChannel<SfxChannel>>) {
+    sfx.pause();
+}
+
+/// Resumes the engine as well, `control_flying_sound` pauses it again if it's silent
+fn resume_sounds(sfx: Res<AudioChannel<SfxChannel>>) {
+    sfx.resume();
+}
+
+fn apply_volume(
+    settings: Res<Settings>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+) {
+    music.set_volume(settings.music_volume() as f64);
+    sfx.set_volume(settings.sfx_volume() as f64);
+}
+
+/// Volume and playback rate of the engine at a speed and thrust from 0 to 1
+/// Drifting without input still sounds like flight, thrusting makes it louder and higher on top
+fn engine_mix(speed: f32, thrust: f32) -> (f32, f32) {
+    let volume = FLYING_VOLUME * (speed * 0.7 + thrust * 0.3);
+    let rate =
+        ENGINE_MIN_RATE + (ENGINE_MAX_RATE - ENGINE_MIN_RATE) * speed + ENGINE_THRUST_RATE * thrust;
+    (volume, rate)
+}
+
+fn control_flying_sound(
+    time: Res<Time>,
+    mut flying: ResMut<FlyingAudio>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    player_query: Query<&Velocity, With<Player>>,
+) {
+    let delta = time.delta_seconds();
+    let velocity = player_query
+        .get_single()
+        .map_or(Vec2::ZERO, |velocity| velocity.0);
+    if delta > 0.0 {
+        let acceleration = (velocity - flying.velocity).length() / delta;
+        let target = (acceleration / FULL_THRUST).min(1.0);
+        flying.thrust += (target - flying.thrust) * (1.0 - (-THRUST_SMOOTHING * delta).exp());
+    }
+    flying.velocity = velocity;
+
+    let speed = (velocity.length() / CRUISING_SPEED).min(1.0);
+    let (volume, rate) = engine_mix(speed, flying.thrust);
+    let Some(instance) = audio_instances.get_mut(&flying.instance) else {
+        return;
+    };
+    match instance.state() {
+        PlaybackState::Paused { .. } if volume > ENGINE_SILENT => {
+            instance.resume(AudioTween::linear(ENGINE_FADE));
+        }
+        PlaybackState::Playing { .. } if volume <= ENGINE_SILENT => {
+            instance.pause(AudioTween::linear(ENGINE_FADE));
+        }
+        _ => {}
+    }
+    if (volume - flying.volume).abs() > ENGINE_THRESHOLD
+        || (rate - flying.rate).abs() > ENGINE_THRESHOLD
+    {
+        instance.set_volume(volume as f64, AudioTween::linear(ENGINE_TWEEN));
+        instance.set_playback_rate(rate as f64, AudioTween::linear(ENGINE_TWEEN));
+        flying.volume = volume;
+        flying.rate = rate;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engine_is_silent_at_rest_and_rises_with_speed_and_thrust() {
+        let (resting, _) = engine_mix(0.0, 0.0);
+        let (drifting, drifting_rate) = engine_mix(0.5, 0.0);
+        let (thrusting, thrusting_rate) = engine_mix(0.5, 1.0);
+        assert_eq!(resting, 0.0);
+        assert!(drifting > ENGINE_SILENT);
+        assert!(thrusting > drifting);
+        assert!(thrusting_rate > drifting_rate);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::audio::MusicChannel;
+use crate::combat::Health;
+use crate::enemy::Enemy;
+use crate::loading::AudioAssets;
+use crate::player::Player;
+use crate::wave::Wave;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use std::time::Duration;
+
+const CROSSFADE: Duration = Duration::from_millis(1500);
+/// Music plays at this part of its volume while the game is paused
+const PAUSED_VOLUME: f32 = 0.4;
+/// This many enemies at once make for the most hectic music
+const SWARM_SIZE: f32 = 40.0;
+/// How many waves it takes for the wave number alone to push the music as far as it goes
+const LATE_WAVE: f32 = 10.0;
+/// Smaller volume changes wait until they add up, so the layers aren't retweened every frame
+const MIX_THRESHOLD: f32 = 0.02;
+
+/// Stems of the same length and tempo, all playing at once and faded in and out by how hectic the run is
+#[derive(Clone, Copy)]
+enum Layer {
+    Calm,
+    Combat,
+    /// There is no boss yet, so this comes in when a run is at its most hectic
+    Boss,
+}
+
+const LAYERS: [Layer; 3] = [Layer::Calm, Layer::Combat, Layer::Boss];
+
+impl Layer {
+    fn source(self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Layer::Calm => audio_assets.calm_music.clone(),
+            Layer::Combat => audio_assets.combat_music.clone(),
+            Layer::Boss => audio_assets.boss_music.clone(),
+        }
+    }
+
+    /// Volume of this layer at an intensity from 0 to 1
+    fn volume(self, intensity: f32) -> f32 {
+        let fade_in = |from: f32, to: f32| ((intensity - from) / (to - from)).clamp(0.0, 1.0);
+        match self {
+            Layer::Calm => 1.0 - 0.7 * fade_in(0.3, 0.8),
+            Layer::Combat => fade_in(0.15, 0.5),
+            Layer::Boss => fade_in(0.75, 1.0),
+        }
+    }
+}
+
+/// How hectic the run is right now, from 0 to 1
+/// Crowds of enemies matter most, low health and later waves push it further
+fn intensity(enemies: usize, wave: u32, health: f32) -> f32 {
+    let swarm = (enemies as f32 / SWARM_SIZE).min(1.0);
+    let progress = (wave.saturating_sub(1) as f32 / LATE_WAVE).min(1.0);
+    let danger = 1.0 - health.clamp(0.0, 1.0);
+    (swarm * 0.6 + progress * 0.2 + danger * 0.4).min(1.0)
+}
+
+/// The stems of the current run and the volumes they're fading towards
+#[derive(Resource)]
+pub(super) struct MusicLayers {
+    instances: [Handle<AudioInstance>; 3],
+    volumes: [f32; 3],
+}
+
+pub(super) fn start_music(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    music: Res<AudioChannel<MusicChannel>>,
+) {
+    music.stop();
+    let volumes = LAYERS.map(|layer| layer.volume(0.0));
+    let instances = LAYERS.map(|layer| {
+        music
+            .play(layer.source(&audio_assets))
+            .looped()
+            .with_volume(layer.volume(0.0) as f64)
+            .handle()
+    });
+    commands.insert_resource(MusicLayers { instances, volumes });
+}
+
+pub(super) fn stop_music(music: Res<AudioChannel<MusicChannel>>) {
+    music.stop().fade_out(AudioTween::linear(CROSSFADE));
+}
+
+/// Crossfades the stems towards how hectic the run is
+pub(super) fn mix_music(
+    state: Res<State<GameState>>,
+    wave: Option<Res<Wave>>,
+    layers: Option<ResMut<MusicLayers>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    enemy_query: Query<(), With<Enemy>>,
+    player_query: Query<&Health, With<Player>>,
+) {
+    let Some(mut layers) = layers else {
+        return;
+    };
+    let health = player_query
+        .get_single()
+        .map_or(1.0, |health| health.current / health.max);
+    let wave = wave.map_or(1, |wave| wave.number);
+    let intensity = intensity(enemy_query.iter().count(), wave, health);
+    let volume = if *state.get() == GameState::Paused {
+        PAUSED_VOLUME
+    } else {
+        1.0
+    };
+    let MusicLayers { instances, volumes } = &mut *layers;
+    for ((layer, instance), current) in LAYERS.iter().zip(instances.iter()).zip(volumes) {
+        let target = layer.volume(intensity);
+        if (target - *current).abs() < MIX_THRESHOLD && !state.is_changed() {
+            continue;
+        }
+        if let Some(instance) = audio_instances.get_mut(instance) {
+            instance.set_volume((target * volume) as f64, AudioTween::linear(CROSSFADE));
+            *current = target;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calm_runs_only_play_the_calm_layer() {
+        let intensity = intensity(0, 1, 1.0);
+        assert_eq!(intensity, 0.0);
+        assert_eq!(Layer::Calm.volume(intensity), 1.0);
+        assert_eq!(Layer::Combat.volume(intensity), 0.0);
+        assert_eq!(Layer::Boss.volume(intensity), 0.0);
+    }
+
+    #[test]
+    fn swarms_at_low_health_bring_in_every_layer() {
+        let intensity = intensity(60, 5, 0.2);
+        assert_eq!(intensity, 1.0);
+        assert!(Layer::Calm.volume(intensity) > 0.0);
+        assert_eq!(Layer::Combat.volume(intensity), 1.0);
+        assert_eq!(Layer::Boss.volume(intensity), 1.0);
+    }
+}
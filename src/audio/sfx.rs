@@ -0,0 +1,261 @@
+use crate::audio::SfxChannel;
+use crate::camera::CameraRig;
+use crate::combat::DamageDealt;
+use crate::enemy::EnemyDied;
+use crate::loading::AudioAssets;
+use crate::player::Player;
+use crate::run::GameplayEntity;
+use crate::wave::WaveCompleted;
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+
+/// Sounds further than this from the camera are silent, in world units at zoom 1
+const HEARING_RANGE: f32 = 2400.0;
+/// How far sounds at the edge of the view lean to one side, 1 would put them all the way into one ear
+const MAX_PANNING: f64 = 0.8;
+
+/// Something worth hearing happened, gameplay code sends these and the audio plugin decides how they sound
+#[derive(Event, Clone, Copy)]
+pub struct SoundEvent {
+    pub sfx: Sfx,
+    /// Where it happened, sounds without a position play in the middle at full volume
+    pub position: Option<Vec2>,
+}
+
+impl SoundEvent {
+    pub fn at(sfx: Sfx, position: Vec2) -> Self {
+        Self {
+            sfx,
+            position: Some(position),
+        }
+    }
+}
+
+impl From<Sfx> for SoundEvent {
+    fn from(sfx: Sfx) -> Self {
+        Self {
+            sfx,
+            position: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sfx {
+    Shot,
+    Hit,
+    EnemyDied,
+    PlayerHurt,
+    PickupCollected,
+    WaveStarted,
+}
+
+impl Sfx {
+    /// One of these is picked at random every time the sound plays
+    fn sounds(self, audio_assets: &AudioAssets) -> &[Handle<AudioSource>] {
+        match self {
+            Sfx::Shot => &audio_assets.shots,
+            Sfx::Hit => &audio_assets.hits,
+            Sfx::EnemyDied => &audio_assets.explosions,
+            Sfx::PlayerHurt => &audio_assets.hurts,
+            Sfx::PickupCollected => &audio_assets.pickups,
+            Sfx::WaveStarted => std::slice::from_ref(&audio_assets.wave_start),
+        }
+    }
+
+    fn volume(self) -> f64 {
+        match self {
+            Sfx::Shot => 0.3,
+            Sfx::Hit => 0.5,
+            Sfx::EnemyDied => 0.7,
+            Sfx::PlayerHurt => 0.8,
+            Sfx::PickupCollected => 0.4,
+            Sfx::WaveStarted => 0.8,
+        }
+    }
+
+    /// Playback rate and volume are each off by up to this part, so repeated sounds don't drone
+    fn variation(self) -> f64 {
+        match self {
+            Sfx::Shot | Sfx::PickupCollected => 0.1,
+            Sfx::Hit | Sfx::EnemyDied => 0.15,
+            Sfx::PlayerHurt => 0.05,
+            Sfx::WaveStarted => 0.0,
+        }
+    }
+
+    /// More of the same sound at once only gets louder and muddier, so further ones are dropped
+    fn max_voices(self) -> usize {
+        match self {
+            Sfx::Shot => 6,
+            Sfx::Hit => 8,
+            Sfx::EnemyDied => 6,
+            Sfx::PlayerHurt => 2,
+            Sfx::PickupCollected => 4,
+            Sfx::WaveStarted => 1,
+        }
+    }
+}
+
+/// Every sound effect still playing
+#[derive(Resource, Default)]
+pub(super) struct Voices(Vec<(Sfx, Handle<AudioInstance>)>);
+
+/// A sound playing somewhere in the world, kept panned and attenuated relative to the camera until it ends
+#[derive(Component)]
+pub(super) struct SoundEmitter {
+    instance: Handle<AudioInstance>,
+    position: Vec2,
+    volume: f64,
+}
+
+/// Volume and panning of a sound at `offset` from the middle of the view
+/// Sounds on screen are panned by where they are on it, sounds off screen fade out with distance
+// bevy_kira_audio's spatial audio doesn't fit here: its emitters get their volume set outright from
+// the distance every frame, which throws away each sound's own volume and variation, and it measures
+// that distance in 3D from the camera, which sits far above the 2D world
+fn spatial_mix(offset: Vec2, projection: &OrthographicProjection) -> (f64, f64) {
+    let range = HEARING_RANGE * projection.scale;
+    let volume = (1.0 - offset.length() / range).clamp(0.0, 1.0).powi(2);
+    let side = (offset.x / projection.area.half_size().x).clamp(-1.0, 1.0);
+    (volume as f64, 0.5 + side as f64 * MAX_PANNING / 2.0)
+}
+
+/// Translates gameplay events into sounds, for the ones that don't send a [`SoundEvent`] themselves
+pub(super) fn sounds_from_gameplay(
+    mut sounds: EventWriter<SoundEvent>,
+    mut damage_dealt_events: EventReader<DamageDealt>,
+    mut enemy_died_events: EventReader<EnemyDied>,
+    mut wave_completed_events: EventReader<WaveCompleted>,
+    player_query: Query<(), With<Player>>,
+) {
+    for event in damage_dealt_events.read() {
+        if player_query.contains(event.target) {
+            sounds.send(SoundEvent::at(Sfx::PlayerHurt, event.position));
+        } else {
+            sounds.send(SoundEvent::at(Sfx::Hit, event.position));
+        }
+    }
+    for event in enemy_died_events.read() {
+        sounds.send(SoundEvent::at(Sfx::EnemyDied, event.position));
+    }
+    // a wave is completed the moment the next one starts
+    for _ in wave_completed_events.read() {
+        sounds.send(Sfx::WaveStarted.into());
+    }
+}
+
+pub(super) fn play_sounds(
+    mut commands: Commands,
+    mut sound_events: EventReader<SoundEvent>,
+    mut voices: ResMut<Voices>,
+    audio: Res<AudioChannel<SfxChannel>>,
+    audio_assets: Res<AudioAssets>,
+    camera_query: Query<(&OrthographicProjection, &CameraRig)>,
+) {
+    voices
+        .0
+        .retain(|(_, instance)| audio.state(instance) != PlaybackState::Stopped);
+    let camera = camera_query.get_single().ok();
+    let mut rng = thread_rng();
+
+    for event in sound_events.read() {
+        let sfx = event.sfx;
+        let (mix, panning) = match (event.position, camera) {
+            (Some(position), Some((projection, rig))) => {
+                spatial_mix(position - rig.position, projection)
+            }
+            _ => (1.0, 0.5),
+        };
+        // out of earshot, don't take up a voice
+        if mix <= 0.0 {
+            continue;
+        }
+        let playing = voices.0.iter().filter(|(other, _)| *other == sfx).count();
+        if playing >= sfx.max_voices() {
+            continue;
+        }
+        let Some(sound) = sfx.sounds(&audio_assets).choose(&mut rng) else {
+            continue;
+        };
+
+        let variation = sfx.variation();
+        let mut vary = || 1.0 + rng.gen_range(-variation..=variation);
+        let volume = sfx.volume() * vary();
+        let rate = vary();
+        let instance = audio
+            .play(sound.clone())
+            .with_volume(volume * mix)
+            .with_panning(panning)
+            .with_playback_rate(rate)
+            .handle();
+
+        if let Some(position) = event.position {
+            commands.spawn((
+                SoundEmitter {
+                    instance: instance.clone(),
+                    position,
+                    volume,
+                },
+                GameplayEntity,
+            ));
+        }
+        voices.0.push((sfx, instance));
+    }
+}
+
+/// The camera keeps moving while sounds play, so their mix follows it
+pub(super) fn update_sound_emitters(
+    mut commands: Commands,
+    audio: Res<AudioChannel<SfxChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    emitter_query: Query<(Entity, &SoundEmitter)>,
+    camera_query: Query<(&OrthographicProjection, &CameraRig)>,
+) {
+    let Ok((projection, rig)) = camera_query.get_single() else {
+        return;
+    };
+    for (entity, emitter) in emitter_query.iter() {
+        if audio.state(&emitter.instance) == PlaybackState::Stopped {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let Some(instance) = audio_instances.get_mut(&emitter.instance) else {
+            continue;
+        };
+        let (mix, panning) = spatial_mix(emitter.position - rig.position, projection);
+        instance.set_volume(emitter.volume * mix, AudioTween::default());
+        instance.set_panning(panning, AudioTween::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projection() -> OrthographicProjection {
+        OrthographicProjection {
+            area: Rect::from_center_half_size(Vec2::ZERO, Vec2::new(640.0, 360.0)),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn sounds_pan_towards_their_side_of_the_view() {
+        let (center_volume, center) = spatial_mix(Vec2::new(0.0, 100.0), &projection());
+        let (_, left) = spatial_mix(Vec2::new(-320.0, 0.0), &projection());
+        let (_, right) = spatial_mix(Vec2::new(2000.0, 0.0), &projection());
+        assert_eq!(center, 0.5);
+        assert!(left < 0.5);
+        assert_eq!(right, 0.5 + MAX_PANNING / 2.0);
+        assert!(center_volume > 0.9);
+    }
+
+    #[test]
+    fn far_away_sounds_are_silent() {
+        let (volume, _) = spatial_mix(Vec2::new(0.0, HEARING_RANGE + 1.0), &projection());
+        assert_eq!(volume, 0.0);
+    }
+}
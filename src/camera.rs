@@ -1,16 +1,154 @@
-use crate::actions::Actions;
+use crate::actions::{Actions, AimSource};
+use crate::combat::{DamageDealt, Health};
+use crate::gravity::GravitySource;
+use crate::map::MAP_RADIUS;
+use crate::movement::Velocity;
 use crate::player::Player;
+use crate::run::RunSetup;
+use crate::settings::Settings;
 use crate::GameState;
-use bevy::ecs::query::QuerySingleError;
 use bevy::prelude::*;
 
+/// How quickly the camera catches up with its focus, higher is snappier
+const FOLLOW_FREQUENCY: f32 = 6.0;
+/// The player can move this far from the focus before the camera follows
+const DEADZONE: f32 = 40.0;
+/// Seconds of the player's velocity the camera looks ahead
+const VELOCITY_LOOK_AHEAD: f32 = 0.4;
+/// How far the camera leans into the direction the player aims with the mouse, a stick or touch
+const AIM_LOOK_AHEAD: f32 = 120.0;
+const MAX_LOOK_AHEAD: f32 = 250.0;
+/// How much of the area outside the map may be in view
+const VIEW_MARGIN: f32 = 200.0;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+const ZOOM_SMOOTHING: f32 = 8.0;
+
+/// Trauma lost per second, a full shake settles in well under a second
+const TRAUMA_DECAY: f32 = 1.5;
+const MAX_SHAKE_OFFSET: f32 = 30.0;
+/// Radians
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+const SHAKE_FREQUENCY: f32 = 15.0;
+/// Zoom punches fade by this factor every second
+const PUNCH_DECAY: f32 = 12.0;
+/// Losing this much of the player's health at once shakes the camera as hard as it goes
+const FULL_TRAUMA_HIT: f32 = 0.3;
+/// Hits taking off more than this part of the player's health punch the zoom as well
+const BIG_HIT: f32 = 0.15;
+const BIG_HIT_PUNCH: f32 = 0.06;
+const BLACK_HOLE_RUMBLE_RANGE: f32 = 600.0;
+/// Trauma per second right at a black hole's center
+const BLACK_HOLE_RUMBLE: f32 = 1.2;
+
 pub struct CameraPlugin;
 
-/// This plugin handles player related stuff like movement
-/// Player logic is only active during the State `GameState::Playing`
+/// This plugin moves the camera after the player with a damped spring, looking ahead of where they're going
+/// Anything can shake the camera on top of that by sending a [`CameraImpulse`]
+/// The camera is only moved during the State `GameState::Playing`
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, move_camera.run_if(in_state(GameState::Playing)));
+        app.add_event::<CameraImpulse>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(OnEnter(GameState::Playing), reset_camera.in_set(RunSetup))
+            .add_systems(
+                Update,
+                (
+                    (shake_on_player_hit, rumble_near_black_holes),
+                    move_camera,
+                    shake_camera,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Shakes the camera, shakes from several impulses add up
+#[derive(Event)]
+pub struct CameraImpulse {
+    /// From 0 to 1, the shake grows with its square so small impulses stay subtle
+    pub trauma: f32,
+    /// Briefly zooms in by this part of the current zoom
+    pub zoom_punch: f32,
+}
+
+impl CameraImpulse {
+    pub fn shake(trauma: f32) -> Self {
+        Self {
+            trauma,
+            zoom_punch: 0.0,
+        }
+    }
+}
+
+/// Trauma based shake, layered on top of where the [`CameraRig`] puts the camera
+#[derive(Component, Default)]
+struct CameraShake {
+    trauma: f32,
+    punch: f32,
+    /// Drives the shake's noise, only advances while shaking
+    time: f32,
+}
+
+/// Where the camera is heading and how fast, kept apart from its `Transform` so effects can be layered on top
+#[derive(Component)]
+pub struct CameraRig {
+    /// The point the camera follows, it only moves once the player leaves the deadzone around it
+    focus: Vec2,
+    pub position: Vec2,
+    velocity: Vec2,
+    /// Projection scale the camera zooms towards
+    target_zoom: f32,
+    pub zoom: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: Vec2::ZERO,
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            target_zoom: 1.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl CameraRig {
+    /// Critically damped spring towards the focus, stable for any frame time
+    fn follow(&mut self, delta: f32) {
+        let offset = self.position - self.focus;
+        let decay = (-FOLLOW_FREQUENCY * delta).exp();
+        let change = (self.velocity + FOLLOW_FREQUENCY * offset) * delta;
+        self.velocity = (self.velocity - FOLLOW_FREQUENCY * change) * decay;
+        self.position = self.focus + (offset + change) * decay;
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle::default(),
+        CameraRig::default(),
+        CameraShake::default(),
+    ));
+}
+
+fn reset_camera(
+    mut camera_query: Query<(
+        &mut OrthographicProjection,
+        &mut Transform,
+        &mut CameraRig,
+        &mut CameraShake,
+    )>,
+) {
+    for (mut camera_proj, mut camera_transform, mut rig, mut shake) in camera_query.iter_mut() {
+        *rig = CameraRig::default();
+        *shake = CameraShake::default();
+        camera_proj.scale = rig.zoom;
+        camera_transform.translation.x = 0.0;
+        camera_transform.translation.y = 0.0;
+        camera_transform.rotation = Quat::IDENTITY;
     }
 }
 
@@ -18,33 +156,129 @@ fn move_camera(
     time: Res<Time>,
     actions: Res<Actions>,
     mut camera_query: Query<
-        (&mut OrthographicProjection, &mut Transform),
-        (With<Camera>, Without<Player>),
+        (&mut OrthographicProjection, &mut Transform, &mut CameraRig),
+        Without<Player>,
     >,
-    player_query: Query<&Transform, (With<Player>, Without<Camera>)>,
+    player_query: Query<(&Transform, &Velocity), (With<Player>, Without<CameraRig>)>,
 ) {
-    let player_transform = match player_query.get_single() {
-        Ok(transform) => Some(*transform),
-        Err(e) => match e {
-            QuerySingleError::NoEntities(_) => None,
-            QuerySingleError::MultipleEntities(_) => {
-                eprintln!("Error: Multiple player entities found!");
-                None
+    let delta = time.delta_seconds();
+    for (mut camera_proj, mut camera_transform, mut rig) in camera_query.iter_mut() {
+        if let Some(zoom) = actions.camera_movement.map(|movement| movement.z) {
+            rig.target_zoom = (rig.target_zoom * (zoom * delta).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        let zoom_step = 1.0 - (-ZOOM_SMOOTHING * delta).exp();
+        rig.zoom += (rig.target_zoom - rig.zoom) * zoom_step;
+
+        if let Ok((player_transform, velocity)) = player_query.get_single() {
+            // auto aim jumps between targets, only lean into aim the player chose
+            let aim = match actions.aim.source {
+                AimSource::Mouse | AimSource::Stick | AimSource::Touch => actions.aim.direction,
+                AimSource::None | AimSource::Auto => None,
+            };
+            let look_ahead = (velocity.0 * VELOCITY_LOOK_AHEAD
+                + aim.unwrap_or(Vec2::ZERO) * AIM_LOOK_AHEAD)
+                .clamp_length_max(MAX_LOOK_AHEAD);
+            let wanted = player_transform.translation.truncate() + look_ahead;
+            let outside = wanted - rig.focus;
+            if outside.length() > DEADZONE {
+                rig.focus = wanted - outside.normalize() * DEADZONE;
             }
-        },
+        }
+        rig.follow(delta);
+
+        // keep the view from showing more than a strip of the void around the map
+        let half_view = camera_proj.area.half_size() / camera_proj.scale * rig.zoom;
+        let max_distance = (MAP_RADIUS + VIEW_MARGIN - half_view.min_element()).max(0.0);
+        rig.position = rig.position.clamp_length_max(max_distance);
+
+        camera_proj.scale = rig.zoom;
+        camera_transform.translation.x = rig.position.x;
+        camera_transform.translation.y = rig.position.y;
+    }
+}
+
+fn shake_on_player_hit(
+    mut damage_dealt_events: EventReader<DamageDealt>,
+    mut impulses: EventWriter<CameraImpulse>,
+    player_query: Query<&Health, With<Player>>,
+) {
+    for event in damage_dealt_events.read() {
+        let Ok(health) = player_query.get(event.target) else {
+            continue;
+        };
+        let lost = event.amount / health.max;
+        impulses.send(CameraImpulse {
+            trauma: (lost / FULL_TRAUMA_HIT).min(1.0),
+            zoom_punch: if lost >= BIG_HIT { BIG_HIT_PUNCH } else { 0.0 },
+        });
+    }
+}
+
+/// Black holes rumble the closer the player gets to one
+fn rumble_near_black_holes(
+    time: Res<Time>,
+    mut impulses: EventWriter<CameraImpulse>,
+    player_query: Query<&Transform, With<Player>>,
+    black_hole_query: Query<&Transform, With<GravitySource>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
     };
+    let closest = black_hole_query
+        .iter()
+        .map(|transform| {
+            transform
+                .translation
+                .truncate()
+                .distance(player_transform.translation.truncate())
+        })
+        .min_by(f32::total_cmp);
+    if let Some(distance) = closest.filter(|distance| *distance < BLACK_HOLE_RUMBLE_RANGE) {
+        let closeness = 1.0 - distance / BLACK_HOLE_RUMBLE_RANGE;
+        impulses.send(CameraImpulse::shake(
+            BLACK_HOLE_RUMBLE * closeness * time.delta_seconds(),
+        ));
+    }
+}
 
-    for (mut camera_proj, mut camera_transform) in camera_query.iter_mut() {
-        if let Some(player_transform) = player_transform {
-            camera_transform.translation.x = player_transform.translation.x;
-            camera_transform.translation.y = player_transform.translation.y;
-        }
+/// Smooth noise from -1 to 1 made of two sines that don't line up
+fn wobble(time: f32, seed: f32) -> f32 {
+    (time + seed).sin() * 0.6 + (time * 2.3 + seed * 1.7).sin() * 0.4
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut impulses: EventReader<CameraImpulse>,
+    mut camera_query: Query<(
+        &mut OrthographicProjection,
+        &mut Transform,
+        &mut CameraShake,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    let (trauma, punch) =
+        impulses
+            .read()
+            .fold((0.0, 0.0), |(trauma, punch): (f32, f32), impulse| {
+                (trauma + impulse.trauma, punch.max(impulse.zoom_punch))
+            });
 
-        if actions.camera_movement.is_none() {
-            return;
+    for (mut camera_proj, mut camera_transform, mut shake) in camera_query.iter_mut() {
+        shake.trauma = (shake.trauma + trauma - TRAUMA_DECAY * delta).clamp(0.0, 1.0);
+        shake.punch = shake.punch.max(punch) * (-PUNCH_DECAY * delta).exp();
+        if shake.trauma > 0.0 {
+            shake.time += delta * SHAKE_FREQUENCY;
         }
 
-        let zoom_move = actions.camera_movement.unwrap().z * time.delta_seconds();
-        camera_proj.scale += zoom_move;
+        // the rig put the camera in place this frame, so the shake never adds up
+        let amount = shake.trauma * shake.trauma * settings.screen_shake;
+        let offset = Vec2::new(wobble(shake.time, 0.0), wobble(shake.time, 10.0))
+            * MAX_SHAKE_OFFSET
+            * amount;
+        camera_transform.translation += offset.extend(0.0);
+        camera_transform.rotation =
+            Quat::from_rotation_z(wobble(shake.time, 20.0) * MAX_SHAKE_ANGLE * amount);
+        camera_proj.scale *= 1.0 - shake.punch * settings.screen_shake.min(1.0);
     }
 }
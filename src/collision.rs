@@ -1,3 +1,5 @@
+use crate::movement::position_update;
+use crate::settings::Settings;
 use crate::{GameState, GameplaySet};
 use bevy::math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume};
 use bevy::prelude::*;
@@ -6,17 +8,22 @@ pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<CollisionEvent>().add_systems(
-            PostUpdate,
-            (
-                update_hitbox_positions,
-                render_hitbox_gizmos, // todo: toggleable/debug
-                detect_collisions,
+        // collisions are detected once everything moved, so `GameplaySet::Collisions` reacts to this tick's
+        app.add_event::<CollisionEvent>()
+            .add_systems(
+                FixedUpdate,
+                (update_hitbox_positions, detect_collisions)
+                    .chain()
+                    .after(position_update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(GameplaySet::Physics),
             )
-                .chain()
-                .run_if(in_state(GameState::Playing))
-                .in_set(GameplaySet::Collisions),
-        );
+            .add_systems(
+                Update,
+                render_hitbox_gizmos
+                    .run_if(|settings: Res<Settings>| settings.show_hitboxes)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -26,10 +33,9 @@ pub enum HitBox {
 }
 
 #[derive(Event)]
-#[allow(unused)]
-struct CollisionEvent {
-    entity1: Entity,
-    entity2: Entity,
+pub struct CollisionEvent {
+    pub entity1: Entity,
+    pub entity2: Entity,
 }
 
 #[derive(Component)]
@@ -63,6 +69,7 @@ pub enum CollisionLayer {
     Enemy,
     PlayerProjectile,
     EnemyProjectile,
+    Pickup,
 }
 
 impl CollisionLayer {
@@ -72,6 +79,10 @@ impl CollisionLayer {
             (CollisionLayer::PlayerProjectile, CollisionLayer::Enemy)
                 | (CollisionLayer::Enemy, CollisionLayer::PlayerProjectile)
                 | (CollisionLayer::EnemyProjectile, CollisionLayer::Player)
+                | (CollisionLayer::Player, CollisionLayer::Enemy)
+                | (CollisionLayer::Enemy, CollisionLayer::Player)
+                | (CollisionLayer::Player, CollisionLayer::Pickup)
+                | (CollisionLayer::Pickup, CollisionLayer::Player)
                 | (
                     CollisionLayer::EnemyProjectile,
                     CollisionLayer::EnemyProjectile
@@ -155,3 +166,53 @@ fn render_hitbox_gizmos(mut gizmos: Gizmos, collider_query: Query<&Collider>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::Enemy;
+    use crate::movement::Velocity;
+    use crate::testing::Harness;
+
+    fn spawn_enemy(harness: &mut Harness, x: f32) -> Entity {
+        harness.spawn((
+            Enemy,
+            TransformBundle::from_transform(Transform::from_xyz(x, 0.0, 0.0)),
+            Collider::new_aabb(CollisionLayer::Enemy, Vec2::splat(20.0)),
+        ))
+    }
+
+    fn spawn_bullet(harness: &mut Harness, x: f32, speed: f32) -> Entity {
+        harness.spawn((
+            TransformBundle::from_transform(Transform::from_xyz(x, 0.0, 0.0)),
+            Velocity(Vec2::new(speed, 0.0)),
+            Collider::new_circle(CollisionLayer::PlayerProjectile, 10.0),
+        ))
+    }
+
+    #[test]
+    fn bullets_hit_enemies_in_their_path() {
+        let mut harness = Harness::new().without_gravity();
+        let enemy = spawn_enemy(&mut harness, 500.0);
+        let bullet = spawn_bullet(&mut harness, 200.0, 1200.0);
+
+        let mut hit = false;
+        for _ in 0..30 {
+            harness.step(1);
+            hit |= harness.collided(enemy, bullet);
+        }
+        assert!(hit);
+    }
+
+    #[test]
+    fn bullets_flying_away_miss() {
+        let mut harness = Harness::new().without_gravity();
+        let enemy = spawn_enemy(&mut harness, 500.0);
+        let bullet = spawn_bullet(&mut harness, 200.0, -1200.0);
+
+        for _ in 0..30 {
+            harness.step(1);
+            assert!(!harness.collided(enemy, bullet));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+
+pub struct CombatPlugin;
+
+/// This plugin turns damage events into health changes and reports deaths
+/// Whoever owns the dying entity decides what to do with it (despawn, recycle, ...)
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DamageDealt>()
+            .add_event::<DeathEvent>()
+            .add_systems(
+                FixedUpdate,
+                apply_damage
+                    .in_set(GameplaySet::Damage)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+}
+
+/// Damage sent to an entity with this component is ignored
+#[derive(Component)]
+pub struct Invulnerable;
+
+#[derive(Event)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub critical: bool,
+}
+
+/// Sent for every damage event that actually took health off its target
+#[derive(Event)]
+pub struct DamageDealt {
+    pub target: Entity,
+    pub position: Vec2,
+    pub amount: f32,
+    pub critical: bool,
+}
+
+#[derive(Event)]
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
+fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut damage_dealt_events: EventWriter<DamageDealt>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut health_query: Query<(&mut Health, &Transform, Has<Invulnerable>)>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut health, transform, invulnerable)) = health_query.get_mut(event.target) else {
+            continue;
+        };
+        // already dead this frame, don't report it twice
+        if invulnerable || health.current <= 0.0 {
+            continue;
+        }
+
+        health.current -= event.amount;
+        damage_dealt_events.send(DamageDealt {
+            target: event.target,
+            position: transform.translation.truncate(),
+            amount: event.amount,
+            critical: event.critical,
+        });
+        if health.current <= 0.0 {
+            death_events.send(DeathEvent {
+                entity: event.target,
+            });
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::combat::{DamageDealt, Health};
+use crate::enemy::Enemy;
+use crate::pool::Pool;
+use crate::run::GameplayEntity;
+use crate::{GameState, ZLayer};
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::{Text2dBounds, TextLayoutInfo};
+use rand::prelude::*;
+use std::time::Duration;
+
+const NUMBER_DURATION: Duration = Duration::from_millis(800);
+/// Hits on an enemy whose last number is younger than this add onto that number
+const NUMBER_STACK_WINDOW: f32 = 0.25;
+const NUMBER_RISE_SPEED: f32 = 60.0;
+const NUMBER_OFFSET: f32 = 30.0;
+const NUMBER_JITTER: f32 = 15.0;
+const NUMBER_COLOR: Color = Color::rgb(1.0, 1.0, 1.0);
+const CRIT_COLOR: Color = Color::rgb(1.0, 0.75, 0.1);
+
+const BAR_SIZE: Vec2 = Vec2::new(50.0, 6.0);
+const BAR_OFFSET: f32 = 40.0;
+/// Bars stay up this long after the last hit, fading out over the last `BAR_FADE`
+const BAR_DURATION: Duration = Duration::from_secs(3);
+const BAR_FADE: f32 = 0.5;
+const BAR_BACKGROUND: Color = Color::rgb(0.1, 0.1, 0.1);
+const BAR_FILL: Color = Color::rgb(0.85, 0.2, 0.25);
+
+pub struct DamageFeedbackPlugin;
+
+/// This plugin shows floating damage numbers and health bars over enemies that got hit
+/// Both are pooled, so a screen full of hits doesn't spawn new entities every frame
+impl Plugin for DamageFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pool<DamageNumberBundle>>()
+            .init_resource::<Pool<HealthBarBundle>>()
+            .add_systems(
+                Update,
+                (
+                    (show_damage_numbers, animate_damage_numbers).chain(),
+                    (
+                        show_health_bars,
+                        attach_health_bar_fills,
+                        update_health_bars,
+                    )
+                        .chain(),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct DamageNumber {
+    target: Entity,
+    amount: f32,
+    critical: bool,
+    timer: Timer,
+}
+
+#[derive(Bundle)]
+struct DamageNumberBundle {
+    number: DamageNumber,
+    text: Text,
+}
+
+#[derive(Component)]
+struct HealthBar {
+    target: Entity,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct HealthBarFill;
+
+#[derive(Bundle)]
+struct HealthBarBundle {
+    bar: HealthBar,
+    sprite: Sprite,
+}
+
+fn damage_text(amount: f32, critical: bool) -> Text {
+    let (font_size, color) = if critical {
+        (32.0, CRIT_COLOR)
+    } else {
+        (24.0, NUMBER_COLOR)
+    };
+    Text::from_section(
+        format!("{amount:.0}"),
+        TextStyle {
+            font_size,
+            color,
+            ..default()
+        },
+    )
+}
+
+fn show_damage_numbers(
+    mut commands: Commands,
+    mut damage_dealt_events: EventReader<DamageDealt>,
+    mut pool: ResMut<Pool<DamageNumberBundle>>,
+    mut number_query: Query<(&mut DamageNumber, &mut Text)>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    // add up all of this frame's hits per enemy first, so they share a number
+    let mut hits: Vec<(Entity, Vec2, f32, bool)> = Vec::new();
+    for event in damage_dealt_events.read() {
+        if !enemy_query.contains(event.target) {
+            continue;
+        }
+        match hits.iter_mut().find(|(target, ..)| *target == event.target) {
+            Some((_, _, amount, critical)) => {
+                *amount += event.amount;
+                *critical |= event.critical;
+            }
+            None => hits.push((event.target, event.position, event.amount, event.critical)),
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    for (target, position, amount, critical) in hits {
+        let recent = number_query.iter_mut().find(|(number, _)| {
+            number.target == target && number.timer.elapsed_secs() < NUMBER_STACK_WINDOW
+        });
+        if let Some((mut number, mut text)) = recent {
+            number.amount += amount;
+            number.critical |= critical;
+            number.timer.reset();
+            *text = damage_text(number.amount, number.critical);
+            continue;
+        }
+
+        let offset = Vec2::new(rng.gen_range(-NUMBER_JITTER..NUMBER_JITTER), NUMBER_OFFSET);
+        let bundle = DamageNumberBundle {
+            number: DamageNumber {
+                target,
+                amount,
+                critical,
+                timer: Timer::new(NUMBER_DURATION, TimerMode::Once),
+            },
+            text: damage_text(amount, critical),
+        };
+        pool.acquire(
+            &mut commands,
+            Transform::from_translation((position + offset).extend(ZLayer::Foreground.into())),
+            bundle,
+            || {
+                (
+                    SpatialBundle::default(),
+                    Anchor::Center,
+                    Text2dBounds::default(),
+                    TextLayoutInfo::default(),
+                    GameplayEntity,
+                )
+            },
+        );
+    }
+}
+
+fn animate_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<DamageNumberBundle>>,
+    mut number_query: Query<(Entity, &mut Transform, &mut Text, &mut DamageNumber)>,
+) {
+    for (entity, mut transform, mut text, mut number) in number_query.iter_mut() {
+        number.timer.tick(time.delta());
+        if number.timer.finished() {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        transform.translation.y += NUMBER_RISE_SPEED * time.delta_seconds();
+        let alpha = 1.0 - number.timer.fraction();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+fn show_health_bars(
+    mut commands: Commands,
+    mut damage_dealt_events: EventReader<DamageDealt>,
+    mut pool: ResMut<Pool<HealthBarBundle>>,
+    mut bar_query: Query<&mut HealthBar>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    let mut shown: Vec<Entity> = Vec::new();
+    for event in damage_dealt_events.read() {
+        if !enemy_query.contains(event.target) || shown.contains(&event.target) {
+            continue;
+        }
+        shown.push(event.target);
+
+        if let Some(mut bar) = bar_query.iter_mut().find(|bar| bar.target == event.target) {
+            bar.timer.reset();
+            continue;
+        }
+
+        let bundle = HealthBarBundle {
+            bar: HealthBar {
+                target: event.target,
+                timer: Timer::new(BAR_DURATION, TimerMode::Once),
+            },
+            sprite: Sprite {
+                color: BAR_BACKGROUND,
+                custom_size: Some(BAR_SIZE),
+                ..default()
+            },
+        };
+        pool.acquire(&mut commands, Transform::default(), bundle, || {
+            (
+                SpatialBundle::default(),
+                Handle::<Image>::default(),
+                GameplayEntity,
+            )
+        });
+    }
+}
+
+/// Gives new health bars their fill, which stays attached while the bar is pooled
+fn attach_health_bar_fills(
+    mut commands: Commands,
+    bar_query: Query<Entity, (With<HealthBar>, Without<Children>)>,
+) {
+    for entity in bar_query.iter() {
+        commands.entity(entity).with_children(|bar| {
+            bar.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: BAR_FILL,
+                        custom_size: Some(BAR_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(-BAR_SIZE.x / 2.0, 0.0, 0.1),
+                    ..default()
+                },
+                HealthBarFill,
+            ));
+        });
+    }
+}
+
+fn update_health_bars(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<HealthBarBundle>>,
+    mut bar_query: Query<
+        (
+            Entity,
+            &mut HealthBar,
+            &mut Transform,
+            &mut Sprite,
+            Option<&Children>,
+        ),
+        Without<HealthBarFill>,
+    >,
+    mut fill_query: Query<&mut Sprite, (With<HealthBarFill>, Without<HealthBar>)>,
+    enemy_query: Query<(&Transform, &Health), (With<Enemy>, Without<HealthBar>)>,
+) {
+    for (entity, mut bar, mut transform, mut sprite, children) in bar_query.iter_mut() {
+        bar.timer.tick(time.delta());
+        let target = enemy_query
+            .get(bar.target)
+            .ok()
+            .filter(|(_, health)| health.current > 0.0);
+        let Some((target_transform, health)) = target.filter(|_| !bar.timer.finished()) else {
+            pool.release(&mut commands, entity);
+            continue;
+        };
+
+        transform.translation = (target_transform.translation.truncate()
+            + Vec2::new(0.0, BAR_OFFSET))
+        .extend(ZLayer::Foreground.into());
+
+        let remaining = bar.timer.remaining_secs();
+        let alpha = (remaining / BAR_FADE).min(1.0);
+        sprite.color.set_a(alpha);
+
+        let fraction = (health.current / health.max).clamp(0.0, 1.0);
+        for child in children.into_iter().flatten() {
+            if let Ok(mut fill) = fill_query.get_mut(*child) {
+                fill.custom_size = Some(Vec2::new(BAR_SIZE.x * fraction, BAR_SIZE.y));
+                fill.color.set_a(alpha);
+            }
+        }
+    }
+}
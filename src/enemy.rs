@@ -1,17 +1,100 @@
-use crate::collision::{Collider, CollisionLayer};
-use crate::loading::TextureAssets;
+use crate::collision::{Collider, CollisionEvent, CollisionLayer};
+use crate::combat::{DamageEvent, DeathEvent, Health};
+use crate::loading::{sprite_or_placeholder, TextureAssets};
 use crate::map::MAP_RADIUS;
 use crate::movement::{Mass, PhysicsBundle};
+use crate::pickup::{DropChance, PickupKind, PowerUpKind};
 use crate::player::Player;
+use crate::pool::Pool;
+use crate::run::{GameRng, GameplayEntity, RunSetup};
+use crate::wave::Wave;
 use crate::{GameState, GameplaySet, ZLayer};
 use bevy::prelude::*;
 use rand::prelude::*;
 
 pub struct EnemyPlugin;
 
+/// Damage per second dealt to the player while touching an enemy
+const CONTACT_DAMAGE: f32 = 20.0;
+
 #[derive(Component)]
 pub struct Enemy;
 
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+    Grunt,
+}
+
+impl EnemyKind {
+    /// Size of the sprite in world units, the collider covers all of it
+    pub fn size(self) -> Vec2 {
+        match self {
+            EnemyKind::Grunt => Vec2::new(32.0, 32.0),
+        }
+    }
+
+    /// Score awarded for killing this kind of enemy
+    pub fn score(self) -> u32 {
+        match self {
+            EnemyKind::Grunt => 10,
+        }
+    }
+
+    /// What this kind of enemy may leave behind when it dies
+    pub fn drop_table(self) -> &'static [DropChance] {
+        match self {
+            EnemyKind::Grunt => &GRUNT_DROPS,
+        }
+    }
+}
+
+const GRUNT_DROPS: [DropChance; 7] = [
+    DropChance {
+        pickup: PickupKind::Experience(1),
+        chance: 1.0,
+    },
+    DropChance {
+        pickup: PickupKind::Health(20.0),
+        chance: 0.03,
+    },
+    DropChance {
+        pickup: PickupKind::Bomb,
+        chance: 0.005,
+    },
+    DropChance {
+        pickup: PickupKind::PowerUp(PowerUpKind::RapidFire),
+        chance: 0.01,
+    },
+    DropChance {
+        pickup: PickupKind::PowerUp(PowerUpKind::Shield),
+        chance: 0.01,
+    },
+    DropChance {
+        pickup: PickupKind::PowerUp(PowerUpKind::Speed),
+        chance: 0.01,
+    },
+    DropChance {
+        pickup: PickupKind::PowerUp(PowerUpKind::Magnet),
+        chance: 0.01,
+    },
+];
+
+/// Sent when an enemy is killed, after it has been recycled
+#[derive(Event)]
+pub struct EnemyDied {
+    pub position: Vec2,
+    pub kind: EnemyKind,
+}
+
+#[derive(Bundle)]
+pub struct EnemyBundle {
+    enemy: Enemy,
+    kind: EnemyKind,
+    physics: PhysicsBundle,
+    collider: Collider,
+    health: Health,
+}
+
 #[derive(Component)]
 pub struct Spawner {
     timer: Timer,
@@ -19,14 +102,24 @@ pub struct Spawner {
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
+        app.add_event::<EnemyDied>()
+            .init_resource::<Pool<EnemyBundle>>()
+            .add_systems(OnEnter(GameState::Playing), setup.in_set(RunSetup))
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
                     move_enemy.run_if(in_state(GameState::Playing)),
                     spawn_enemy.run_if(in_state(GameState::Playing)),
                 )
                     .in_set(GameplaySet::EnemyUpdate),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    contact_damage.in_set(GameplaySet::Collisions),
+                    recycle_dead_enemies.in_set(GameplaySet::Deaths),
+                )
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -40,48 +133,61 @@ impl Spawner {
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Spawner::new(5.0));
+    commands.spawn((Spawner::new(5.0), GameplayEntity));
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_enemy(
     mut commands: Commands,
     textures: Res<TextureAssets>,
     time: Res<Time>,
+    wave: Res<Wave>,
     mut spawner_query: Query<&mut Spawner>,
     image_assets: Res<Assets<Image>>,
+    mut pool: ResMut<Pool<EnemyBundle>>,
+    mut rng: ResMut<GameRng>,
 ) {
-    // could cache this
-    let mut rng = rand::thread_rng();
     for mut spawner in spawner_query.iter_mut() {
+        spawner.timer.set_duration(wave.spawn_interval());
         spawner.timer.tick(time.delta());
-        if spawner.timer.finished() {
+        if !spawner.timer.finished() {
+            continue;
+        }
+
+        let kind = EnemyKind::Grunt;
+        let size = kind.size();
+        let (texture, sprite) = sprite_or_placeholder(&textures.monster1, &image_assets, size);
+
+        for _ in 0..wave.spawn_count() {
             let rand_x = rng.gen_range(-MAP_RADIUS..MAP_RADIUS);
             let rand_y = rng.gen_range(-MAP_RADIUS..MAP_RADIUS);
-            let texture = textures.monster1.clone();
-            let Some(image_data) = image_assets.get(texture.clone()) else {
-                panic!("Failed to get image data for enemy spawn");
-            };
-            let size = Vec2::new(
-                image_data.texture_descriptor.size.width as f32,
-                image_data.texture_descriptor.size.height as f32,
-            );
-
-            commands
-                .spawn(SpriteBundle {
-                    texture,
-                    transform: Transform::from_translation(Vec3::new(
-                        rand_x,
-                        rand_y,
-                        f32::from(ZLayer::Character) + 1.0,
-                    )),
-                    ..Default::default()
-                })
-                .insert(Enemy)
-                .insert(PhysicsBundle {
+            let transform = Transform::from_translation(Vec3::new(
+                rand_x,
+                rand_y,
+                f32::from(ZLayer::Character) + 1.0,
+            ));
+            let bundle = EnemyBundle {
+                enemy: Enemy,
+                kind,
+                physics: PhysicsBundle {
                     mass: Mass(5.),
                     ..default()
-                })
-                .insert(Collider::new_aabb(CollisionLayer::Enemy, size / 2.0));
+                },
+                collider: Collider::new_aabb(CollisionLayer::Enemy, size / 2.0),
+                health: Health::new(100.0),
+            };
+
+            let (texture, sprite) = (texture.clone(), sprite.clone());
+            pool.acquire(&mut commands, transform, bundle, || {
+                (
+                    SpriteBundle {
+                        texture,
+                        sprite,
+                        ..Default::default()
+                    },
+                    GameplayEntity,
+                )
+            });
         }
     }
 }
@@ -102,3 +208,87 @@ fn move_enemy(
         enemy_transform.translation += movement;
     }
 }
+
+fn recycle_dead_enemies(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    mut enemy_died_events: EventWriter<EnemyDied>,
+    mut pool: ResMut<Pool<EnemyBundle>>,
+    enemy_query: Query<(&Transform, &EnemyKind), With<Enemy>>,
+) {
+    for event in death_events.read() {
+        if let Ok((transform, kind)) = enemy_query.get(event.entity) {
+            pool.release(&mut commands, event.entity);
+            enemy_died_events.send(EnemyDied {
+                position: transform.translation.truncate(),
+                kind: *kind,
+            });
+        }
+    }
+}
+
+fn contact_damage(
+    time: Res<Time>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    player_query: Query<(), With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    for event in collision_events.read() {
+        let player = if player_query.contains(event.entity1) && enemy_query.contains(event.entity2)
+        {
+            event.entity1
+        } else if player_query.contains(event.entity2) && enemy_query.contains(event.entity1) {
+            event.entity2
+        } else {
+            continue;
+        };
+        // collisions are reported every frame the two overlap
+        damage_events.send(DamageEvent {
+            target: player,
+            amount: CONTACT_DAMAGE * time.delta_seconds(),
+            critical: false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Harness;
+
+    fn enemy_positions(harness: &mut Harness) -> Vec<Vec3> {
+        harness
+            .query::<&Transform, With<Enemy>>()
+            .iter()
+            .map(|transform| transform.translation)
+            .collect()
+    }
+
+    #[test]
+    fn first_wave_spawns_on_its_interval() {
+        let mut harness = Harness::new();
+        let wave = harness.app.world.resource::<Wave>();
+        let (interval, count) = (wave.spawn_interval(), wave.spawn_count());
+        let tick = harness.app.world.resource::<Time<Fixed>>().timestep();
+
+        // three and a half spawn intervals
+        let ticks = (interval.as_secs_f32() * 3.5 / tick.as_secs_f32()) as u32;
+        harness.step(ticks);
+
+        assert_eq!(enemy_positions(&mut harness).len(), 3 * count as usize);
+    }
+
+    #[test]
+    fn spawns_are_the_same_for_the_same_seed() {
+        let mut first = Harness::new();
+        let mut second = Harness::new();
+
+        first.step(400);
+        second.step(400);
+
+        let positions = enemy_positions(&mut first);
+        assert!(!positions.is_empty());
+        assert_eq!(positions, enemy_positions(&mut second));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::player::Player;
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+
+pub struct ExperiencePlugin;
+
+/// This plugin levels the player up once they've collected enough experience pickups
+/// Levelling up moves the game to `GameState::LevelUp`, where an upgrade gets picked
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            check_level_up
+                .in_set(GameplaySet::Rewards)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Level {
+    pub level: u32,
+    pub xp: u32,
+    pub xp_to_next: u32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            xp: 0,
+            xp_to_next: xp_for_level(1),
+        }
+    }
+}
+
+/// Experience needed to go from `level` to the next one
+pub fn xp_for_level(level: u32) -> u32 {
+    5 + level * 5
+}
+
+fn check_level_up(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut player_query: Query<&mut Level, With<Player>>,
+) {
+    let Ok(mut level) = player_query.get_single_mut() else {
+        return;
+    };
+
+    // any experience left over counts toward the next level, which may trigger right after this one
+    if level.xp >= level.xp_to_next {
+        level.xp -= level.xp_to_next;
+        level.level += 1;
+        level.xp_to_next = xp_for_level(level.level);
+        next_state.set(GameState::LevelUp);
+    }
+}
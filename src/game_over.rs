@@ -0,0 +1,75 @@
+use crate::experience::Level;
+use crate::menu::{spawn_button, text_style, ChangeState};
+use crate::player::Player;
+use crate::run::RunStats;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct GameOverPlugin;
+
+/// This plugin shows how the run went once the player dies
+/// From here the player can start a new run or go back to the menu
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), setup_game_over)
+            .add_systems(OnExit(GameState::GameOver), cleanup_game_over);
+    }
+}
+
+#[derive(Component)]
+struct GameOver;
+
+fn setup_game_over(
+    mut commands: Commands,
+    stats: Res<RunStats>,
+    player_query: Query<&Level, With<Player>>,
+) {
+    let level = player_query.get_single().map_or(1, |level| level.level);
+    let seconds = stats.time.as_secs();
+    let summary = [
+        format!("Survived {}:{:02}", seconds / 60, seconds % 60),
+        format!("Reached level {level}"),
+        format!("Defeated {} enemies", stats.kills),
+        format!("Score {}", stats.score),
+    ];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            GameOver,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section("Game Over", text_style(60.0)));
+            for line in summary {
+                children.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font_size: 25.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                        ..default()
+                    },
+                ));
+            }
+            for (label, state) in [("Restart", GameState::Playing), ("Menu", GameState::Menu)] {
+                spawn_button(children, label, 140.0, ChangeState(state));
+            }
+        });
+}
+
+fn cleanup_game_over(mut commands: Commands, screen: Query<Entity, With<GameOver>>) {
+    for entity in screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
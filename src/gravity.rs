@@ -1,4 +1,5 @@
 use crate::movement::{Force, Mass};
+use crate::run::{GameplayEntity, RunSetup};
 use crate::{loading::TextureAssets, GameState, GameplaySet};
 use bevy::prelude::*;
 
@@ -13,9 +14,9 @@ pub struct GravitySource {
 
 impl Plugin for GravityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
+        app.add_systems(OnEnter(GameState::Playing), setup.in_set(RunSetup))
             .add_systems(
-                Update,
+                FixedUpdate,
                 force_apply_gravity
                     .run_if(in_state(GameState::Playing))
                     .in_set(GameplaySet::PrePhysics),
@@ -32,7 +33,8 @@ fn setup(mut commands: Commands, textures: Res<TextureAssets>) {
             ..Default::default()
         })
         .insert(GravitySource { max_range: 2000.0 })
-        .insert(Mass(2000000.0));
+        .insert(Mass(2000000.0))
+        .insert(GameplayEntity);
     commands
         .spawn(SpriteBundle {
             texture: textures.black_hole.clone(),
@@ -41,7 +43,8 @@ fn setup(mut commands: Commands, textures: Res<TextureAssets>) {
             ..Default::default()
         })
         .insert(GravitySource { max_range: 2500.0 })
-        .insert(Mass(6000000.0));
+        .insert(Mass(6000000.0))
+        .insert(GameplayEntity);
     commands
         .spawn(SpriteBundle {
             texture: textures.black_hole.clone(),
@@ -50,7 +53,8 @@ fn setup(mut commands: Commands, textures: Res<TextureAssets>) {
             ..Default::default()
         })
         .insert(GravitySource { max_range: 15000.0 })
-        .insert(Mass(250000000.0));
+        .insert(Mass(250000000.0))
+        .insert(GameplayEntity);
 }
 
 fn force_apply_gravity(
@@ -0,0 +1,348 @@
+use crate::combat::Health;
+use crate::experience::Level;
+use crate::player::Player;
+use crate::run::{GameplayEntity, RunSetup, RunStats};
+use crate::score::Combo;
+use crate::wave::Wave;
+use crate::weapon::Arsenal;
+use crate::GameState;
+use bevy::prelude::*;
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const BAR_BACKGROUND: Color = Color::rgba(0.1, 0.1, 0.1, 0.7);
+const HEALTH_COLOR: Color = Color::rgb(0.85, 0.2, 0.25);
+const XP_COLOR: Color = Color::rgb(0.3, 0.9, 0.6);
+const COOLDOWN_COLOR: Color = Color::rgb(0.4, 0.7, 1.0);
+const ACTIVE_WEAPON_BORDER: Color = Color::rgb(1.0, 0.85, 0.3);
+
+pub struct HudPlugin;
+
+/// This plugin shows the state of the run on top of the game
+/// Everything is laid out relative to the window edges so it fits desktop and phone screens alike
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), setup_hud.in_set(RunSetup))
+            .add_systems(
+                Update,
+                (
+                    update_bars,
+                    update_hud_text,
+                    sync_weapon_slots,
+                    update_weapon_slots,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+enum HudBar {
+    Health,
+    Experience,
+}
+
+#[derive(Component)]
+enum HudText {
+    Level,
+    Wave,
+    Timer,
+    Kills,
+    Score,
+    Combo,
+}
+
+/// Holds one [`WeaponSlot`] per weapon in the player's arsenal
+#[derive(Component)]
+struct WeaponSlots;
+
+#[derive(Component)]
+struct WeaponSlot(usize);
+
+#[derive(Component)]
+struct WeaponLabel(usize);
+
+#[derive(Component)]
+struct WeaponCooldown(usize);
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, bar: HudBar, color: Color, height: f32) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Px(height),
+                ..default()
+            },
+            background_color: BAR_BACKGROUND.into(),
+            ..default()
+        })
+        .with_children(|background| {
+            background.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                bar,
+            ));
+        });
+}
+
+fn setup_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            GameplayEntity,
+        ))
+        .with_children(|hud| {
+            // top row: player on the left, wave in the middle, run stats on the right
+            hud.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Start,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|top| {
+                top.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(30.0),
+                        max_width: Val::Px(400.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|player| {
+                    player.spawn((
+                        TextBundle::from_section("", text_style(20.0)),
+                        HudText::Level,
+                    ));
+                    spawn_bar(player, HudBar::Health, HEALTH_COLOR, 16.0);
+                    spawn_bar(player, HudBar::Experience, XP_COLOR, 8.0);
+                });
+                top.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|wave| {
+                    wave.spawn((
+                        TextBundle::from_section("", text_style(28.0)),
+                        HudText::Wave,
+                    ));
+                    wave.spawn((
+                        TextBundle::from_section("", text_style(20.0)),
+                        HudText::Timer,
+                    ));
+                });
+                top.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::End,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|stats| {
+                    stats.spawn((
+                        TextBundle::from_section("", text_style(24.0)),
+                        HudText::Score,
+                    ));
+                    stats.spawn((
+                        TextBundle::from_section("", text_style(20.0)),
+                        HudText::Combo,
+                    ));
+                    stats.spawn((
+                        TextBundle::from_section("", text_style(20.0)),
+                        HudText::Kills,
+                    ));
+                });
+            });
+
+            // bottom row: weapon cooldowns, wrapping onto more lines on narrow screens
+            hud.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        row_gap: Val::Px(6.0),
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                WeaponSlots,
+            ));
+        });
+}
+
+fn update_bars(
+    player_query: Query<(&Health, &Level), With<Player>>,
+    mut bar_query: Query<(&mut Style, &HudBar)>,
+) {
+    let Ok((health, level)) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut style, bar) in bar_query.iter_mut() {
+        let fraction = match bar {
+            HudBar::Health => health.current / health.max,
+            HudBar::Experience => level.xp as f32 / level.xp_to_next as f32,
+        };
+        style.width = Val::Percent(fraction.clamp(0.0, 1.0) * 100.0);
+    }
+}
+
+fn update_hud_text(
+    stats: Res<RunStats>,
+    wave: Res<Wave>,
+    combo: Res<Combo>,
+    player_query: Query<&Level, With<Player>>,
+    mut text_query: Query<(&mut Text, &HudText)>,
+) {
+    let level = player_query.get_single().map_or(1, |level| level.level);
+    let seconds = stats.time.as_secs();
+    let next_wave = wave.timer.remaining().as_secs_f32().ceil() as u32;
+
+    for (mut text, hud_text) in text_query.iter_mut() {
+        text.sections[0].value = match hud_text {
+            HudText::Level => format!("Level {level}"),
+            HudText::Wave => format!("Wave {} - next in {next_wave}s", wave.number),
+            HudText::Timer => format!("{}:{:02}", seconds / 60, seconds % 60),
+            HudText::Kills => format!("Kills {}", stats.kills),
+            HudText::Score => format!("Score {}", stats.score),
+            HudText::Combo if combo.kills > 1 => {
+                format!("{} combo x{:.1}", combo.kills, combo.multiplier())
+            }
+            HudText::Combo => String::new(),
+        };
+    }
+}
+
+/// Rebuilds the weapon slots whenever the arsenal gains a weapon
+fn sync_weapon_slots(
+    mut commands: Commands,
+    player_query: Query<&Arsenal, With<Player>>,
+    slots_query: Query<(Entity, Option<&Children>), With<WeaponSlots>>,
+) {
+    let (Ok(arsenal), Ok((container, children))) =
+        (player_query.get_single(), slots_query.get_single())
+    else {
+        return;
+    };
+    if children.map_or(0, |children| children.len()) == arsenal.weapons.len() {
+        return;
+    }
+
+    commands.entity(container).despawn_descendants();
+    commands.entity(container).with_children(|slots| {
+        for index in 0..arsenal.weapons.len() {
+            slots
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(110.0),
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(2.0),
+                            padding: UiRect::all(Val::Px(4.0)),
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        background_color: BAR_BACKGROUND.into(),
+                        ..default()
+                    },
+                    WeaponSlot(index),
+                ))
+                .with_children(|slot| {
+                    slot.spawn((
+                        TextBundle::from_section("", text_style(14.0)),
+                        WeaponLabel(index),
+                    ));
+                    slot.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(6.0),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                        ..default()
+                    })
+                    .with_children(|bar| {
+                        bar.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                background_color: COOLDOWN_COLOR.into(),
+                                ..default()
+                            },
+                            WeaponCooldown(index),
+                        ));
+                    });
+                });
+        }
+    });
+}
+
+fn update_weapon_slots(
+    player_query: Query<&Arsenal, With<Player>>,
+    mut slot_query: Query<(&mut BorderColor, &WeaponSlot)>,
+    mut label_query: Query<(&mut Text, &WeaponLabel)>,
+    mut cooldown_query: Query<(&mut Style, &WeaponCooldown)>,
+) {
+    let Ok(arsenal) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut border, slot) in slot_query.iter_mut() {
+        *border = if slot.0 == arsenal.active {
+            ACTIVE_WEAPON_BORDER.into()
+        } else {
+            Color::NONE.into()
+        };
+    }
+    for (mut text, label) in label_query.iter_mut() {
+        if let Some(weapon) = arsenal.weapons.get(label.0) {
+            text.sections[0].value = format!("{} {}", weapon.id.definition().name, weapon.level);
+        }
+    }
+    for (mut style, cooldown) in cooldown_query.iter_mut() {
+        if let Some(weapon) = arsenal.weapons.get(cooldown.0) {
+            style.width = Val::Percent(weapon.timer.fraction() * 100.0);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::app::RunFixedMainLoop;
+use bevy::prelude::*;
+use bevy::time::run_fixed_main_schedule;
+
+pub struct InterpolationPlugin;
+
+/// This plugin draws moving entities between where the last two gameplay ticks left them
+/// Gameplay advances at a fixed rate, without this anything it moves would step forward every few frames
+/// Between frames the `Transform` of an [`Interpolated`] entity is the drawn one, ticks only ever see their own
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                restore_tick_positions.before(run_fixed_main_schedule),
+                interpolate_positions.after(run_fixed_main_schedule),
+            ),
+        )
+        .add_systems(FixedLast, record_tick_positions);
+    }
+}
+
+/// Drawn between the last two ticks, one tick behind the simulation
+#[derive(Component, Default)]
+pub struct Interpolated {
+    /// Translations at the end of the previous and the latest tick
+    /// `None` until the entity's first tick ended, so new and reused entities don't slide in from elsewhere
+    ticks: Option<(Vec3, Vec3)>,
+}
+
+impl Interpolated {
+    fn at(&self, fraction: f32) -> Option<Vec3> {
+        self.ticks
+            .map(|(previous, latest)| previous.lerp(latest, fraction))
+    }
+}
+
+fn restore_tick_positions(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        if let Some((_, latest)) = interpolated.ticks {
+            transform.translation = latest;
+        }
+    }
+}
+
+// runs on every tick, also while gameplay is frozen, so frozen entities stay put instead of swinging between old positions
+fn record_tick_positions(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        let previous = interpolated
+            .ticks
+            .map_or(transform.translation, |(_, latest)| latest);
+        interpolated.ticks = Some((previous, transform.translation));
+    }
+}
+
+fn interpolate_positions(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &Interpolated)>,
+) {
+    let fraction = time.overstep_fraction();
+    for (mut transform, interpolated) in query.iter_mut() {
+        if let Some(translation) = interpolated.at(fraction) {
+            transform.translation = translation;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_are_drawn_between_the_last_two_ticks() {
+        let mut interpolated = Interpolated::default();
+        assert_eq!(interpolated.at(0.5), None);
+
+        interpolated.ticks = Some((Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0)));
+        assert_eq!(interpolated.at(0.0), Some(Vec3::ZERO));
+        assert_eq!(interpolated.at(0.5), Some(Vec3::new(5.0, 0.0, 0.0)));
+    }
+}
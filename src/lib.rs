@@ -4,28 +4,69 @@ mod actions;
 mod audio;
 mod camera;
 mod collision;
+mod combat;
+mod damage_feedback;
 mod enemy;
+mod experience;
+mod game_over;
 mod gravity;
+mod hud;
+mod interpolation;
 mod loading;
 mod map;
 mod menu;
+mod minimap;
 mod movement;
+mod pause;
+mod pickup;
 mod player;
+mod pool;
+mod projectile;
+mod replay;
+mod run;
+mod score;
+mod settings;
+mod storage;
+#[cfg(test)]
+mod testing;
+mod upgrade;
+mod wave;
+mod weapon;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::camera::CameraPlugin as CustomCameraPlugin;
 use crate::collision::CollisionPlugin;
+use crate::combat::CombatPlugin;
+use crate::damage_feedback::DamageFeedbackPlugin;
 use crate::enemy::EnemyPlugin;
+use crate::experience::ExperiencePlugin;
+use crate::game_over::GameOverPlugin;
+use crate::hud::HudPlugin;
+use crate::interpolation::InterpolationPlugin;
 use crate::loading::LoadingPlugin;
 use crate::map::MapPlugin;
 use crate::menu::MenuPlugin;
+use crate::minimap::MinimapPlugin;
 use crate::movement::MovementPlugin;
+use crate::pause::PausePlugin;
+use crate::pickup::PickupPlugin;
 use crate::player::PlayerPlugin;
+use crate::projectile::ProjectilePlugin;
+use crate::replay::ReplayPlugin;
+use crate::run::RunPlugin;
+use crate::score::ScorePlugin;
+use crate::settings::SettingsPlugin;
+use crate::upgrade::UpgradePlugin;
+use crate::wave::WavePlugin;
+use crate::weapon::WeaponPlugin;
+
+pub use crate::settings::Settings;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::ecs::schedule::ExecutorKind;
 use bevy::prelude::*;
 
 // This example game uses States to separate logic
@@ -38,10 +79,17 @@ enum GameState {
     Loading,
     // During this State the actual game logic is executed
     Playing,
+    // Gameplay is frozen while the player picks an upgrade
+    LevelUp,
+    // Gameplay is frozen until the player resumes
+    Paused,
+    // The player died, the run's stats are shown until they restart or go back to the menu
+    GameOver,
     // Here the menu is drawn and waiting for player interaction
     Menu,
 }
 
+/// Gameplay runs in these sets on `FixedUpdate`, see `configure_gameplay_sets`
 #[derive(SystemSet, Clone, Eq, PartialEq, Debug, Hash)]
 enum GameplaySet {
     PlayerUpdate,
@@ -49,7 +97,14 @@ enum GameplaySet {
     Physics,
     PrePhysics,
     InputHandling,
+    /// Reacts to the collisions detected this tick, which can send `DamageEvent`s
     Collisions,
+    /// Applies this tick's damage
+    Damage,
+    /// Removes whatever that damage killed
+    Deaths,
+    /// Counts, scores and drops loot for this tick's kills
+    Rewards,
 }
 
 #[repr(u8)]
@@ -70,6 +125,42 @@ impl From<ZLayer> for f32 {
     }
 }
 
+/// Gameplay advances this many ticks per second, however fast frames are drawn
+const TICK_RATE: f64 = 60.0;
+
+// gameplay only advances while playing, menus and overlays freeze it
+// it runs on a fixed tick so runs, and replays of them, play out the same at any frame rate
+// the fixed schedule runs single threaded, so systems without an order between them still run in the same order every tick
+fn configure_gameplay_sets(app: &mut App) {
+    app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+        .edit_schedule(FixedUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        })
+        .configure_sets(
+            FixedUpdate,
+            (
+                GameplaySet::InputHandling,
+                GameplaySet::PlayerUpdate,
+                GameplaySet::EnemyUpdate,
+                GameplaySet::PrePhysics,
+                GameplaySet::Physics,
+                GameplaySet::Collisions,
+                GameplaySet::Damage,
+                GameplaySet::Deaths,
+                GameplaySet::Rewards,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing))
+                .run_if(no_pending_transition),
+        );
+}
+
+/// Several ticks can run in one frame, but a state change only applies in the next one
+/// Gameplay waits for it, so where the ticks fall between frames doesn't change how a run plays out
+fn no_pending_transition(next_state: Res<NextState<GameState>>) -> bool {
+    next_state.0.is_none()
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
@@ -84,25 +175,34 @@ impl Plugin for GamePlugin {
             CustomCameraPlugin,
             // GravityPlugin,
             MovementPlugin,
+            InterpolationPlugin,
             MapPlugin,
             CollisionPlugin,
+            CombatPlugin,
+            ProjectilePlugin,
+            WeaponPlugin,
+            (
+                ExperiencePlugin,
+                UpgradePlugin,
+                PickupPlugin,
+                RunPlugin,
+                GameOverPlugin,
+                PausePlugin,
+                SettingsPlugin,
+                WavePlugin,
+                HudPlugin,
+                DamageFeedbackPlugin,
+                ScorePlugin,
+                ReplayPlugin,
+                MinimapPlugin,
+            ),
         ));
 
+        configure_gameplay_sets(app);
+
         #[cfg(debug_assertions)]
         {
-            app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
-                .configure_sets(
-                    Update,
-                    (
-                        GameplaySet::InputHandling,
-                        GameplaySet::PlayerUpdate,
-                        GameplaySet::EnemyUpdate,
-                        GameplaySet::PrePhysics,
-                        GameplaySet::Physics,
-                        GameplaySet::Collisions,
-                    )
-                        .chain(),
-                );
+            app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
         }
     }
 }
@@ -3,6 +3,9 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
 
+/// Shown instead of textures that failed to load
+const PLACEHOLDER_COLOR: Color = Color::rgb(1.0, 0.0, 1.0);
+
 pub struct LoadingPlugin;
 
 /// This plugin loads all assets using [`AssetLoader`] from a third party bevy plugin
@@ -26,6 +29,39 @@ impl Plugin for LoadingPlugin {
 pub struct AudioAssets {
     #[asset(path = "audio/flying.ogg")]
     pub flying: Handle<AudioSource>,
+    #[asset(
+        paths("audio/sfx/shot_1.wav", "audio/sfx/shot_2.wav", "audio/sfx/shot_3.wav"),
+        collection(typed)
+    )]
+    pub shots: Vec<Handle<AudioSource>>,
+    #[asset(
+        paths("audio/sfx/hit_1.wav", "audio/sfx/hit_2.wav", "audio/sfx/hit_3.wav"),
+        collection(typed)
+    )]
+    pub hits: Vec<Handle<AudioSource>>,
+    #[asset(
+        paths("audio/sfx/explosion_1.wav", "audio/sfx/explosion_2.wav"),
+        collection(typed)
+    )]
+    pub explosions: Vec<Handle<AudioSource>>,
+    #[asset(
+        paths("audio/sfx/hurt_1.wav", "audio/sfx/hurt_2.wav"),
+        collection(typed)
+    )]
+    pub hurts: Vec<Handle<AudioSource>>,
+    #[asset(
+        paths("audio/sfx/pickup_1.wav", "audio/sfx/pickup_2.wav"),
+        collection(typed)
+    )]
+    pub pickups: Vec<Handle<AudioSource>>,
+    #[asset(path = "audio/sfx/wave_start.wav")]
+    pub wave_start: Handle<AudioSource>,
+    #[asset(path = "audio/music/calm.wav")]
+    pub calm_music: Handle<AudioSource>,
+    #[asset(path = "audio/music/combat.wav")]
+    pub combat_music: Handle<AudioSource>,
+    #[asset(path = "audio/music/boss.wav")]
+    pub boss_music: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -47,3 +83,48 @@ pub struct ShaderAssets {
     #[asset(path = "shaders/custom_material.wgsl")]
     pub custom_material: Handle<Shader>,
 }
+
+/// A sprite drawing `texture` at `size`, or a flat placeholder of that size if the texture isn't loaded
+/// Gameplay sizes come from data, so nothing depends on the image itself
+pub fn sprite_or_placeholder(
+    texture: &Handle<Image>,
+    images: &Assets<Image>,
+    size: Vec2,
+) -> (Handle<Image>, Sprite) {
+    let sprite = Sprite {
+        custom_size: Some(size),
+        ..default()
+    };
+    if images.contains(texture) {
+        (texture.clone(), sprite)
+    } else {
+        // the default handle is a white pixel
+        let placeholder = Sprite {
+            color: PLACEHOLDER_COLOR,
+            ..sprite
+        };
+        (Handle::default(), placeholder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_textures_fall_back_to_a_placeholder() {
+        let mut images = Assets::<Image>::default();
+        let size = Vec2::new(32.0, 32.0);
+
+        let missing = Handle::weak_from_u128(1);
+        let (texture, sprite) = sprite_or_placeholder(&missing, &images, size);
+        assert_eq!(texture, Handle::default());
+        assert_eq!(sprite.color, PLACEHOLDER_COLOR);
+        assert_eq!(sprite.custom_size, Some(size));
+
+        let loaded = images.add(Image::default());
+        let (texture, sprite) = sprite_or_placeholder(&loaded, &images, size);
+        assert_eq!(texture, loaded);
+        assert_eq!(sprite.custom_size, Some(size));
+    }
+}
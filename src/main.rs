@@ -6,10 +6,11 @@ use bevy::{
     asset::AssetMetaCheck, prelude::*, window::PrimaryWindow, winit::WinitWindows, DefaultPlugins,
 };
 use std::io::Cursor;
-use wave_guy::GamePlugin;
+use wave_guy::{GamePlugin, Settings};
 use winit::window::Icon;
 
 fn main() {
+    let settings = Settings::load();
     App::new()
         .insert_resource(Msaa::Off)
         .insert_resource(AssetMetaCheck::Never)
@@ -21,12 +22,17 @@ fn main() {
                 canvas: Some("#bevy".to_owned()),
                 // Tells wasm not to override default event handling, like F5 and Ctrl+R
                 prevent_default_event_handling: false,
-                present_mode: bevy::window::PresentMode::AutoNoVsync,
-                resolution: WindowResolution::new(1600.0, 1200.0),
+                present_mode: settings.present_mode(),
+                mode: settings.window_mode,
+                resolution: WindowResolution::new(
+                    settings.resolution.0 as f32,
+                    settings.resolution.1 as f32,
+                ),
                 ..default()
             }),
             ..default()
         }))
+        .insert_resource(settings)
         .add_plugins(GamePlugin)
         .add_systems(Startup, set_window_icon)
         .run();
@@ -1,4 +1,6 @@
 use crate::movement::Velocity;
+use crate::projectile::Projectile;
+use crate::run::{GameplayEntity, RunSetup};
 use crate::{GameState, GameplaySet, ZLayer};
 use bevy::prelude::*;
 use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
@@ -12,9 +14,9 @@ pub struct MapBoundary;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
+        app.add_systems(OnEnter(GameState::Playing), setup.in_set(RunSetup))
             .add_systems(
-                Update,
+                FixedUpdate,
                 map_boundary_system
                     .in_set(GameplaySet::Collisions)
                     .run_if(in_state(GameState::Playing)),
@@ -38,11 +40,12 @@ fn setup(
             transform: Transform::from_xyz(0.0, 0.0, ZLayer::Map.into()),
             ..default()
         })
-        .insert(MapBoundary);
+        .insert(MapBoundary)
+        .insert(GameplayEntity);
 }
 
 fn map_boundary_system(
-    mut query: Query<(&mut Transform, &mut Velocity), Without<MapBoundary>>,
+    mut query: Query<(&mut Transform, &mut Velocity), (Without<MapBoundary>, Without<Projectile>)>,
     boundary_query: Query<&Transform, With<MapBoundary>>,
 ) {
     let boundary_transform = boundary_query.single();
@@ -59,6 +62,37 @@ fn map_boundary_system(
     }
 }
 
-fn reflect_velocity(velocity: Vec2, normal: Vec2) -> Vec2 {
+pub fn reflect_velocity(velocity: Vec2, normal: Vec2) -> Vec2 {
     velocity - 2.0 * velocity.dot(normal) * normal
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Harness;
+
+    #[test]
+    fn entities_leaving_the_map_bounce_back() {
+        let mut harness = Harness::new().without_gravity();
+        let entity = harness.spawn((
+            TransformBundle::from_transform(Transform::from_xyz(MAP_RADIUS - 1.0, 0.0, 0.0)),
+            Velocity(Vec2::new(300.0, 0.0)),
+        ));
+
+        harness.step(5);
+
+        let position = harness.get::<Transform>(entity).translation.truncate();
+        assert!(position.length() <= MAP_RADIUS);
+        let velocity = harness.get::<Velocity>(entity).0;
+        assert!(
+            velocity.abs_diff_eq(Vec2::new(-300.0, 0.0), 0.01),
+            "{velocity}"
+        );
+    }
+
+    #[test]
+    fn reflection_keeps_the_tangent() {
+        let reflected = reflect_velocity(Vec2::new(3.0, 4.0), Vec2::Y);
+        assert_eq!(reflected, Vec2::new(3.0, -4.0));
+    }
+}
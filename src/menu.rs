@@ -1,4 +1,7 @@
 use crate::loading::TextureAssets;
+use crate::replay::{LastReplay, WatchReplay};
+use crate::score::HighScores;
+use crate::settings::{spawn_settings_panel, ToggleSettings};
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -9,15 +12,24 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, click_play_button.run_if(in_state(GameState::Menu)))
+            // buttons on other screens use the same components, so this runs in every state
+            .add_systems(Update, navigate_with_gamepad.in_set(MenuNavigation))
+            .add_systems(Update, click_play_button.after(MenuNavigation))
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
 
+/// Gamepad navigation presses buttons by setting their `Interaction` for a frame
+/// Systems reacting to button presses run after this set, or they can miss those presses
+#[derive(SystemSet, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct MenuNavigation;
+
+const FOCUS_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+
 #[derive(Component)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+pub struct ButtonColors {
+    pub normal: Color,
+    pub hovered: Color,
 }
 
 impl Default for ButtonColors {
@@ -32,9 +44,13 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
+fn setup_menu(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    high_scores: Res<HighScores>,
+    last_replay: Res<LastReplay>,
+) {
     info!("menu");
-    commands.spawn(Camera2dBundle::default());
     commands
         .spawn((
             NodeBundle {
@@ -44,6 +60,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
                     ..default()
                 },
                 ..default()
@@ -78,6 +95,12 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
                         },
                     ));
                 });
+            if last_replay.0.is_some() {
+                spawn_button(children, "Watch replay", 200.0, WatchReplay);
+            }
+            spawn_button(children, "Settings", 140.0, ToggleSettings);
+            spawn_settings_panel(children);
+            spawn_high_scores(children, &high_scores);
         });
     commands
         .spawn((
@@ -175,8 +198,83 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
         });
 }
 
+pub fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+        ..default()
+    }
+}
+
+pub fn spawn_button(parent: &mut ChildBuilder, label: &str, width: f32, action: impl Bundle) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style(30.0)));
+        });
+}
+
+fn spawn_high_scores(parent: &mut ChildBuilder, high_scores: &HighScores) {
+    let style = TextStyle {
+        font_size: 20.0,
+        color: Color::rgb(0.8, 0.8, 0.8),
+        ..default()
+    };
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                margin: UiRect::top(Val::Px(30.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|table| {
+            table.spawn(TextBundle::from_section(
+                "High scores",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            if high_scores.0.is_empty() {
+                table.spawn(TextBundle::from_section("No runs yet", style.clone()));
+            }
+            for (rank, entry) in high_scores.0.iter().enumerate() {
+                let line = format!(
+                    "{}. {}  -  {}:{:02}, wave {}, level {}",
+                    rank + 1,
+                    entry.score,
+                    entry.time_secs / 60,
+                    entry.time_secs % 60,
+                    entry.wave,
+                    entry.level,
+                );
+                table.spawn(TextBundle::from_section(line, style.clone()));
+            }
+        });
+}
+
 #[derive(Component)]
-struct ChangeState(GameState);
+pub struct ChangeState(pub GameState);
 
 #[derive(Component)]
 struct OpenLink(&'static str);
@@ -215,6 +313,111 @@ fn click_play_button(
     }
 }
 
+/// Moves a highlight between the visible buttons with the D-pad and presses the highlighted one with South (A on Xbox pads)
+fn navigate_with_gamepad(
+    mut commands: Commands,
+    mut focus: Local<Option<Entity>>,
+    mut pressed: Local<Option<Entity>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    mut button_query: Query<
+        (
+            Entity,
+            &Node,
+            &GlobalTransform,
+            &mut Interaction,
+            Option<&mut Outline>,
+        ),
+        With<Button>,
+    >,
+) {
+    // a press from the gamepad only lasts one frame, the mouse would release it otherwise
+    if let Some(entity) = pressed.take() {
+        if let Ok((_, _, _, mut interaction, _)) = button_query.get_mut(entity) {
+            interaction.set_if_neq(Interaction::None);
+        }
+    }
+
+    let just_pressed = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    // UI coordinates grow downwards
+    let direction = [
+        (GamepadButtonType::DPadUp, Vec2::NEG_Y),
+        (GamepadButtonType::DPadDown, Vec2::Y),
+        (GamepadButtonType::DPadLeft, Vec2::NEG_X),
+        (GamepadButtonType::DPadRight, Vec2::X),
+    ]
+    .into_iter()
+    .find_map(|(button_type, direction)| just_pressed(button_type).then_some(direction));
+    let confirm = just_pressed(GamepadButtonType::South);
+    if direction.is_none() && !confirm {
+        return;
+    }
+
+    // hidden buttons, like the ones in a closed settings panel, have no size
+    let visible: Vec<(Entity, Vec2)> = button_query
+        .iter()
+        .filter(|(_, node, ..)| node.size() != Vec2::ZERO)
+        .map(|(entity, _, transform, ..)| (entity, transform.translation().truncate()))
+        .collect();
+    let current = focus.and_then(|focused| visible.iter().find(|(entity, _)| *entity == focused));
+
+    let next = match (current, direction) {
+        (Some((_, from)), Some(direction)) => visible
+            .iter()
+            .filter_map(|(entity, position)| {
+                let offset = *position - *from;
+                let along = offset.dot(direction);
+                // prefer buttons straight ahead over closer ones off to the side
+                let across = offset.perp_dot(direction).abs();
+                (along > 1.0).then_some((*entity, along + across * 2.0))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(entity, _)| entity)
+            .or(*focus),
+        (Some((entity, _)), None) => Some(*entity),
+        // the first input only picks the top left button
+        (None, _) => visible
+            .iter()
+            .min_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)))
+            .map(|(entity, _)| *entity),
+    };
+
+    if confirm && current.is_some() {
+        if let Some(entity) = next {
+            if let Ok((_, _, _, mut interaction, _)) = button_query.get_mut(entity) {
+                *interaction = Interaction::Pressed;
+                *pressed = Some(entity);
+            }
+        }
+    }
+
+    if next != *focus {
+        if let Some(mut outline) = focus
+            .and_then(|entity| button_query.get_mut(entity).ok())
+            .and_then(|(.., outline)| outline)
+        {
+            outline.color = Color::NONE;
+        }
+        if let Some(entity) = next {
+            match button_query.get_mut(entity) {
+                Ok((.., Some(mut outline))) => outline.color = FOCUS_COLOR,
+                _ => {
+                    commands.entity(entity).insert(Outline::new(
+                        Val::Px(3.0),
+                        Val::ZERO,
+                        FOCUS_COLOR,
+                    ));
+                }
+            }
+        }
+        *focus = next;
+    }
+}
+
 fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
     for entity in menu.iter() {
         commands.entity(entity).despawn_recursive();
@@ -0,0 +1,208 @@
+use crate::camera::CameraRig;
+use crate::enemy::Enemy;
+use crate::gravity::GravitySource;
+use crate::map::MAP_RADIUS;
+use crate::pickup::{Pickup, PickupKind};
+use crate::player::Player;
+use crate::run::{GameplayEntity, RunSetup};
+use crate::GameState;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+const MAP_BACKGROUND: Color = Color::rgba(0.05, 0.05, 0.1, 0.6);
+const BOUNDARY_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+const BOUNDARY_DOTS: usize = 48;
+const PLAYER_COLOR: Color = Color::rgb(1.0, 1.0, 1.0);
+const ENEMY_COLOR: Color = Color::rgb(0.9, 0.2, 0.2);
+const BLACK_HOLE_COLOR: Color = Color::rgb(0.6, 0.3, 0.9);
+const EXPERIENCE_COLOR: Color = Color::rgb(0.3, 0.9, 0.6);
+const PICKUP_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+
+/// Only the closest off-screen threats get an arrow, so a swarm doesn't fence in the screen
+const MAX_INDICATORS: usize = 12;
+/// Threats further than this from the edge of the view don't get an arrow
+const INDICATOR_RANGE: f32 = 1500.0;
+/// Distance of the arrows from the edge of the view, in world units at zoom 1
+const INDICATOR_INSET: f32 = 30.0;
+const INDICATOR_SIZE: f32 = 18.0;
+
+pub struct MinimapPlugin;
+
+/// This plugin shows the whole arena in a corner of the screen and points at threats outside the view
+/// The minimap is a plain UI dot map, its dots are reused between frames
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), setup_minimap.in_set(RunSetup))
+            .add_systems(
+                Update,
+                (update_minimap, draw_offscreen_indicators).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Minimap;
+
+/// A dot for something that moves, reused for whatever needs showing each frame
+#[derive(Component)]
+struct MinimapDot;
+
+fn setup_minimap(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(70.0),
+                    width: Val::VMin(25.0),
+                    max_width: Val::Px(200.0),
+                    aspect_ratio: Some(1.0),
+                    ..default()
+                },
+                background_color: MAP_BACKGROUND.into(),
+                ..default()
+            },
+            Minimap,
+            GameplayEntity,
+        ))
+        .with_children(|minimap| {
+            for index in 0..BOUNDARY_DOTS {
+                let position =
+                    Vec2::from_angle(index as f32 / BOUNDARY_DOTS as f32 * TAU) * MAP_RADIUS;
+                minimap.spawn(dot_bundle(position, 2.0, BOUNDARY_COLOR));
+            }
+        });
+}
+
+/// A square dot of `size` pixels centered on where `position` is in the arena
+fn dot_bundle(position: Vec2, size: f32, color: Color) -> NodeBundle {
+    NodeBundle {
+        style: dot_style(position, size),
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+fn dot_style(position: Vec2, size: f32) -> Style {
+    // the minimap spans the arena's bounding square, UI coordinates grow downwards
+    let fraction = (position / MAP_RADIUS) * Vec2::new(0.5, -0.5) + 0.5;
+    Style {
+        position_type: PositionType::Absolute,
+        left: Val::Percent(fraction.x * 100.0),
+        top: Val::Percent(fraction.y * 100.0),
+        width: Val::Px(size),
+        height: Val::Px(size),
+        margin: UiRect::new(
+            Val::Px(-size / 2.0),
+            Val::ZERO,
+            Val::Px(-size / 2.0),
+            Val::ZERO,
+        ),
+        ..default()
+    }
+}
+
+fn update_minimap(
+    mut commands: Commands,
+    minimap_query: Query<Entity, With<Minimap>>,
+    mut dot_query: Query<(Entity, &mut Style, &mut BackgroundColor), With<MinimapDot>>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    black_hole_query: Query<&Transform, With<GravitySource>>,
+    pickup_query: Query<(&Transform, &Pickup)>,
+) {
+    let Ok(minimap) = minimap_query.get_single() else {
+        return;
+    };
+
+    let position = |transform: &Transform| transform.translation.truncate();
+    // drawn in this order, so the player ends up on top
+    let markers = pickup_query
+        .iter()
+        .map(|(transform, pickup)| {
+            let color = match pickup.kind {
+                PickupKind::Experience(_) => EXPERIENCE_COLOR,
+                _ => PICKUP_COLOR,
+            };
+            (position(transform), 3.0, color)
+        })
+        .chain(
+            black_hole_query
+                .iter()
+                .map(|transform| (position(transform), 10.0, BLACK_HOLE_COLOR)),
+        )
+        .chain(
+            enemy_query
+                .iter()
+                .map(|transform| (position(transform), 4.0, ENEMY_COLOR)),
+        )
+        .chain(
+            player_query
+                .iter()
+                .map(|transform| (position(transform), 7.0, PLAYER_COLOR)),
+        );
+
+    let mut dots = dot_query.iter_mut();
+    for (position, size, color) in markers {
+        match dots.next() {
+            Some((_, mut style, mut background)) => {
+                *style = dot_style(position, size);
+                *background = color.into();
+            }
+            None => {
+                commands.entity(minimap).with_children(|minimap| {
+                    minimap.spawn((dot_bundle(position, size, color), MinimapDot));
+                });
+            }
+        }
+    }
+    for (_, mut style, _) in dots {
+        style.display = Display::None;
+    }
+}
+
+/// Arrows along the edge of the view pointing at enemies and black holes that are out of sight
+fn draw_offscreen_indicators(
+    mut gizmos: Gizmos,
+    camera_query: Query<(&OrthographicProjection, &CameraRig)>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    black_hole_query: Query<&Transform, With<GravitySource>>,
+) {
+    let Ok((projection, rig)) = camera_query.get_single() else {
+        return;
+    };
+    // the rig's position leaves out the shake, so the arrows hold still while the screen shakes
+    let center = rig.position;
+    let half_view = projection.area.half_size();
+    let inset = INDICATOR_INSET * projection.scale;
+    let edge = (half_view - inset).max(Vec2::ZERO);
+
+    let mut threats: Vec<(Vec2, f32, Color)> = enemy_query
+        .iter()
+        .map(|transform| (transform.translation.truncate(), ENEMY_COLOR))
+        .chain(
+            black_hole_query
+                .iter()
+                .map(|transform| (transform.translation.truncate(), BLACK_HOLE_COLOR)),
+        )
+        .filter_map(|(position, color)| {
+            let offset = position - center;
+            // how far outside the view the threat is, along the axis it's furthest out on
+            let outside = (offset.abs() - half_view).max_element();
+            (outside > 0.0 && outside < INDICATOR_RANGE).then_some((offset, outside, color))
+        })
+        .collect();
+    threats.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let size = INDICATOR_SIZE * projection.scale;
+    for (offset, _, color) in threats.into_iter().take(MAX_INDICATORS) {
+        let direction = offset.normalize_or_zero();
+        // scale the direction until it touches the inset view rectangle
+        let reach = (edge / direction.abs().max(Vec2::splat(f32::EPSILON))).min_element();
+        let tip = center + direction * reach;
+        let back = tip - direction * size;
+        let side = direction.perp() * size * 0.5;
+        gizmos.linestrip_2d([tip, back + side, back - side, tip], color);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{actions::Actions, player::Player, GameState, GameplaySet};
+use crate::interpolation::Interpolated;
+use crate::pickup::PowerUps;
+use crate::player::{Player, PlayerStats};
+use crate::{actions::Actions, GameState, GameplaySet};
 use bevy::prelude::*;
 
 pub struct MovementPlugin;
@@ -21,6 +24,7 @@ pub struct PhysicsBundle {
     pub mass: Mass,
     pub acceleration: Acceleration,
     pub velocity: Velocity,
+    pub interpolated: Interpolated,
 }
 
 impl Default for PhysicsBundle {
@@ -30,6 +34,7 @@ impl Default for PhysicsBundle {
             mass: Mass(100.),
             acceleration: Acceleration::default(),
             velocity: Velocity::default(),
+            interpolated: Interpolated::default(),
         }
     }
 }
@@ -37,7 +42,7 @@ impl Default for PhysicsBundle {
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
                 apply_input_velocity,
                 acceleration_update.after(apply_input_velocity),
@@ -52,16 +57,15 @@ impl Plugin for MovementPlugin {
 
 fn apply_input_velocity(
     actions: Res<Actions>,
-    mut player_query: Query<&mut Velocity, With<Player>>,
+    mut player_query: Query<(&mut Velocity, &PlayerStats, &PowerUps), With<Player>>,
 ) {
     if actions.player_movement.is_none() {
         return;
     }
 
-    let base_player_speed = 100.0;
     let movement_dir = actions.player_movement.unwrap_or(Vec2::ZERO);
-    for mut velocity in player_query.iter_mut() {
-        velocity.0 = movement_dir * base_player_speed;
+    for (mut velocity, stats, power_ups) in player_query.iter_mut() {
+        velocity.0 = movement_dir * stats.move_speed * power_ups.speed_multiplier();
     }
 }
 
@@ -77,8 +81,42 @@ fn velocity_update(time: Res<Time>, mut velocity_query: Query<(&mut Velocity, &A
     }
 }
 
-fn position_update(time: Res<Time>, mut transform_query: Query<(&mut Transform, &Velocity)>) {
+pub fn position_update(time: Res<Time>, mut transform_query: Query<(&mut Transform, &Velocity)>) {
     for (mut transform, velocity) in transform_query.iter_mut() {
         transform.translation += velocity.0.extend(0.) * time.delta_seconds();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Harness;
+
+    #[test]
+    fn player_moves_with_the_scripted_actions() {
+        let mut harness = Harness::new().without_gravity();
+        harness.actions().player_movement = Some(Vec2::X);
+
+        // one second
+        harness.step(60);
+
+        let position = harness.get::<Transform>(harness.player).translation;
+        let expected = PlayerStats::default().move_speed;
+        assert!((position.x - expected).abs() < 1.0, "{position}");
+        assert!(position.y.abs() < 0.01, "{position}");
+    }
+
+    #[test]
+    fn player_keeps_drifting_without_input() {
+        let mut harness = Harness::new().without_gravity();
+        harness.actions().player_movement = Some(Vec2::Y);
+        harness.step(30);
+        harness.actions().player_movement = None;
+        let stopped_at = harness.get::<Transform>(harness.player).translation;
+
+        harness.step(30);
+
+        let position = harness.get::<Transform>(harness.player).translation;
+        assert!(position.y > stopped_at.y, "{position}");
+    }
+}
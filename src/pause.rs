@@ -0,0 +1,113 @@
+use crate::actions::{not_rebinding, Controls, GameControl};
+use crate::menu::{spawn_button, text_style, ChangeState, MenuNavigation};
+use crate::run::RunStats;
+use crate::settings::{spawn_settings_panel, ToggleSettings};
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct PausePlugin;
+
+/// This plugin pauses and resumes the run and draws the pause menu in between
+/// Gameplay systems only run in `GameState::Playing` and virtual time stands still while paused
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            toggle_pause
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused)))
+                .run_if(not_rebinding),
+        )
+        .add_systems(OnEnter(GameState::Paused), (pause_time, setup_pause_menu))
+        .add_systems(
+            Update,
+            click_restart_button
+                .after(MenuNavigation)
+                .run_if(in_state(GameState::Paused)),
+        )
+        .add_systems(OnExit(GameState::Paused), (resume_time, cleanup_pause_menu));
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct RestartButton;
+
+fn toggle_pause(
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    controls: Controls,
+) {
+    if !controls.just_pressed(GameControl::Pause) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn setup_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section("Paused", text_style(60.0)));
+            spawn_button(children, "Resume", 220.0, ChangeState(GameState::Playing));
+            spawn_button(children, "Settings", 220.0, ToggleSettings);
+            spawn_button(children, "Restart", 220.0, RestartButton);
+            spawn_button(
+                children,
+                "Quit to menu",
+                220.0,
+                ChangeState(GameState::Menu),
+            );
+
+            spawn_settings_panel(children);
+        });
+}
+
+fn click_restart_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut stats: ResMut<RunStats>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RestartButton>)>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            // entering Playing with the run over tears it down and starts a new one
+            stats.over = true;
+            next_state.set(GameState::Playing);
+        }
+    }
+}
+
+fn cleanup_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
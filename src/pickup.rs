@@ -0,0 +1,401 @@
+use crate::audio::{Sfx, SoundEvent};
+use crate::camera::CameraImpulse;
+use crate::collision::{Collider, CollisionEvent, CollisionLayer};
+use crate::combat::{DamageEvent, Health, Invulnerable};
+use crate::enemy::{Enemy, EnemyDied};
+use crate::experience::Level;
+use crate::interpolation::Interpolated;
+use crate::movement::Velocity;
+use crate::player::{Player, PlayerStats};
+use crate::pool::Pool;
+use crate::run::{GameRng, GameplayEntity};
+use crate::{GameState, GameplaySet, ZLayer};
+use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use rand::prelude::*;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+const PICKUP_SIZE: f32 = 10.0;
+const MAGNET_SPEED: f32 = 600.0;
+/// Pickups dropped together are scattered this far around the enemy
+const DROP_SCATTER: f32 = 25.0;
+/// The bomb hits everything this close to the player, roughly the whole screen
+const BOMB_RADIUS: f32 = 1200.0;
+const BOMB_DAMAGE: f32 = 1000.0;
+const BOMB_BLAST_DURATION: Duration = Duration::from_millis(600);
+const BOMB_TRAUMA: f32 = 0.7;
+const BOMB_ZOOM_PUNCH: f32 = 0.1;
+/// Power-up countdown rings are drawn from this radius outward around the player
+const COUNTDOWN_RADIUS: f32 = 70.0;
+const COUNTDOWN_SPACING: f32 = 8.0;
+
+pub struct PickupPlugin;
+
+/// This plugin drops pickups from dead enemies and applies them when the player touches them
+/// Pickups within the player's pickup radius are pulled in, power-ups wear off after a while
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pool<PickupBundle>>()
+            .add_systems(Startup, setup_pickup_assets)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (tick_power_ups, magnetize_pickups)
+                        .chain()
+                        .in_set(GameplaySet::PrePhysics),
+                    collect_pickups.in_set(GameplaySet::Collisions),
+                    drop_pickups.in_set(GameplaySet::Rewards),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (draw_power_up_countdowns, draw_bomb_blasts).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Experience(u32),
+    Health(f32),
+    /// Damages every enemy on screen
+    Bomb,
+    PowerUp(PowerUpKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Weapons recharge twice as fast
+    RapidFire,
+    /// The player takes no damage
+    Shield,
+    Speed,
+    /// Experience anywhere on the map is pulled in
+    Magnet,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::RapidFire,
+        PowerUpKind::Shield,
+        PowerUpKind::Speed,
+        PowerUpKind::Magnet,
+    ];
+
+    fn duration(self) -> Duration {
+        match self {
+            PowerUpKind::RapidFire => Duration::from_secs(8),
+            PowerUpKind::Shield => Duration::from_secs(6),
+            PowerUpKind::Speed => Duration::from_secs(8),
+            PowerUpKind::Magnet => Duration::from_secs(3),
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PowerUpKind::RapidFire => Color::rgb(1.0, 0.6, 0.1),
+            PowerUpKind::Shield => Color::rgb(0.3, 0.6, 1.0),
+            PowerUpKind::Speed => Color::rgb(1.0, 1.0, 0.3),
+            PowerUpKind::Magnet => Color::rgb(0.8, 0.3, 1.0),
+        }
+    }
+}
+
+/// One entry of an enemy's drop table, rolled independently of the others
+pub struct DropChance {
+    pub pickup: PickupKind,
+    pub chance: f32,
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+}
+
+/// Power-ups currently running on the player and the time left on each
+#[derive(Component, Default)]
+pub struct PowerUps {
+    active: Vec<(PowerUpKind, Timer)>,
+}
+
+impl PowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.active.iter().any(|(active, _)| *active == kind)
+    }
+
+    /// Starts the power-up, or restarts its countdown if it is already running
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        self.active.retain(|(active, _)| *active != kind);
+        self.active
+            .push((kind, Timer::new(kind.duration(), TimerMode::Once)));
+    }
+
+    /// How much faster weapon timers run
+    pub fn fire_rate_multiplier(&self) -> f32 {
+        if self.is_active(PowerUpKind::RapidFire) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.is_active(PowerUpKind::Speed) {
+            1.5
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Pickups own their mesh and material, so a recycled entity can come back as a different kind
+#[derive(Bundle)]
+struct PickupBundle {
+    pickup: Pickup,
+    velocity: Velocity,
+    interpolated: Interpolated,
+    collider: Collider,
+    mesh: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+struct BombBlast {
+    timer: Timer,
+}
+
+#[derive(Resource)]
+struct PickupAssets {
+    gem_mesh: Mesh2dHandle,
+    orb_mesh: Mesh2dHandle,
+    gem_material: Handle<ColorMaterial>,
+    health_material: Handle<ColorMaterial>,
+    bomb_material: Handle<ColorMaterial>,
+    /// Indexed by [`PowerUpKind`]
+    power_up_materials: [Handle<ColorMaterial>; 4],
+}
+
+impl PickupAssets {
+    fn visual(&self, kind: PickupKind) -> (Mesh2dHandle, Handle<ColorMaterial>) {
+        match kind {
+            PickupKind::Experience(_) => (self.gem_mesh.clone(), self.gem_material.clone()),
+            PickupKind::Health(_) => (self.orb_mesh.clone(), self.health_material.clone()),
+            PickupKind::Bomb => (self.orb_mesh.clone(), self.bomb_material.clone()),
+            PickupKind::PowerUp(kind) => (
+                self.orb_mesh.clone(),
+                self.power_up_materials[kind as usize].clone(),
+            ),
+        }
+    }
+}
+
+fn setup_pickup_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(PickupAssets {
+        gem_mesh: Mesh2dHandle(meshes.add(RegularPolygon::new(PICKUP_SIZE * 0.8, 4))),
+        orb_mesh: Mesh2dHandle(meshes.add(Circle::new(PICKUP_SIZE))),
+        gem_material: materials.add(Color::rgb(0.3, 0.9, 0.6)),
+        health_material: materials.add(Color::rgb(0.9, 0.2, 0.3)),
+        bomb_material: materials.add(Color::rgb(0.2, 0.2, 0.2)),
+        power_up_materials: PowerUpKind::ALL.map(|kind| materials.add(kind.color())),
+    });
+}
+
+fn drop_pickups(
+    mut commands: Commands,
+    mut enemy_died_events: EventReader<EnemyDied>,
+    mut pool: ResMut<Pool<PickupBundle>>,
+    pickup_assets: Res<PickupAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in enemy_died_events.read() {
+        let drops = event
+            .kind
+            .drop_table()
+            .iter()
+            .filter(|drop| rng.gen::<f32>() < drop.chance)
+            .map(|drop| drop.pickup)
+            .collect::<Vec<_>>();
+
+        for (index, kind) in drops.iter().enumerate() {
+            let offset = if index == 0 {
+                Vec2::ZERO
+            } else {
+                Vec2::from_angle(rng.gen_range(0.0..TAU)) * DROP_SCATTER
+            };
+            let (mesh, material) = pickup_assets.visual(*kind);
+            let bundle = PickupBundle {
+                pickup: Pickup { kind: *kind },
+                velocity: Velocity::default(),
+                interpolated: Interpolated::default(),
+                collider: Collider::new_circle(CollisionLayer::Pickup, PICKUP_SIZE),
+                mesh,
+                material,
+            };
+            pool.acquire(
+                &mut commands,
+                Transform::from_translation((event.position + offset).extend(ZLayer::Game.into())),
+                bundle,
+                || (SpatialBundle::default(), GameplayEntity),
+            );
+        }
+    }
+}
+
+fn magnetize_pickups(
+    player_query: Query<(&Transform, &PlayerStats, &PowerUps), With<Player>>,
+    mut pickup_query: Query<(&Transform, &mut Velocity, &Pickup), Without<Player>>,
+) {
+    let Ok((player_transform, stats, power_ups)) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+    let magnet = power_ups.is_active(PowerUpKind::Magnet);
+
+    for (transform, mut velocity, pickup) in pickup_query.iter_mut() {
+        let offset = player_position - transform.translation.truncate();
+        let pulled = offset.length() <= stats.pickup_radius
+            || (magnet && matches!(pickup.kind, PickupKind::Experience(_)));
+        velocity.0 = if pulled {
+            offset.normalize_or_zero() * MAGNET_SPEED
+        } else {
+            Vec2::ZERO
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut impulses: EventWriter<CameraImpulse>,
+    mut sounds: EventWriter<SoundEvent>,
+    mut pool: ResMut<Pool<PickupBundle>>,
+    pickup_query: Query<&Pickup>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut Level, &mut Health, &mut PowerUps),
+        With<Player>,
+    >,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+) {
+    let Ok((player, player_transform, mut level, mut health, mut power_ups)) =
+        player_query.get_single_mut()
+    else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for event in collision_events.read() {
+        let pickup_entity = if event.entity1 == player {
+            event.entity2
+        } else if event.entity2 == player {
+            event.entity1
+        } else {
+            continue;
+        };
+        let Ok(pickup) = pickup_query.get(pickup_entity) else {
+            continue;
+        };
+
+        match pickup.kind {
+            PickupKind::Experience(amount) => level.xp += amount,
+            PickupKind::Health(amount) => health.heal(amount),
+            PickupKind::Bomb => {
+                for (enemy, enemy_transform) in enemy_query.iter() {
+                    let distance = enemy_transform
+                        .translation
+                        .truncate()
+                        .distance(player_position);
+                    if distance <= BOMB_RADIUS {
+                        damage_events.send(DamageEvent {
+                            target: enemy,
+                            amount: BOMB_DAMAGE,
+                            critical: false,
+                        });
+                    }
+                }
+                impulses.send(CameraImpulse {
+                    trauma: BOMB_TRAUMA,
+                    zoom_punch: BOMB_ZOOM_PUNCH,
+                });
+                commands.spawn((
+                    SpatialBundle::from_transform(*player_transform),
+                    BombBlast {
+                        timer: Timer::new(BOMB_BLAST_DURATION, TimerMode::Once),
+                    },
+                    GameplayEntity,
+                ));
+            }
+            PickupKind::PowerUp(kind) => power_ups.activate(kind),
+        }
+        sounds.send(SoundEvent::at(Sfx::PickupCollected, player_position));
+        pool.release(&mut commands, pickup_entity);
+    }
+}
+
+/// Counts down running power-ups and keeps the shield in sync with [`Invulnerable`]
+fn tick_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_query: Query<(Entity, &mut PowerUps, Has<Invulnerable>), With<Player>>,
+) {
+    for (player, mut power_ups, invulnerable) in player_query.iter_mut() {
+        for (_, timer) in power_ups.active.iter_mut() {
+            timer.tick(time.delta());
+        }
+        power_ups.active.retain(|(_, timer)| !timer.finished());
+
+        let shielded = power_ups.is_active(PowerUpKind::Shield);
+        if shielded && !invulnerable {
+            commands.entity(player).insert(Invulnerable);
+        } else if !shielded && invulnerable {
+            commands.entity(player).remove::<Invulnerable>();
+        }
+    }
+}
+
+/// Each running power-up gets a ring around the player that shrinks as it runs out
+fn draw_power_up_countdowns(
+    mut gizmos: Gizmos,
+    player_query: Query<(&Transform, &PowerUps), With<Player>>,
+) {
+    for (transform, power_ups) in player_query.iter() {
+        for (index, (kind, timer)) in power_ups.active.iter().enumerate() {
+            let remaining = 1.0 - timer.fraction();
+            gizmos.arc_2d(
+                transform.translation.truncate(),
+                0.0,
+                TAU * remaining,
+                COUNTDOWN_RADIUS + COUNTDOWN_SPACING * index as f32,
+                kind.color(),
+            );
+        }
+    }
+}
+
+fn draw_bomb_blasts(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    mut blast_query: Query<(Entity, &Transform, &mut BombBlast)>,
+) {
+    for (entity, transform, mut blast) in blast_query.iter_mut() {
+        blast.timer.tick(time.delta());
+        if blast.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = blast.timer.fraction();
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            BOMB_RADIUS * progress,
+            Color::rgba(1.0, 0.9, 0.6, 1.0 - progress),
+        );
+    }
+}
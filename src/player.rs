@@ -1,33 +1,69 @@
 #![allow(unused)] // todo: remove eventually
 
-use std::time::Duration;
-use crate::actions::Actions;
 use crate::collision::{Collider, CollisionLayer};
-use crate::loading::TextureAssets;
-use crate::movement::{Mass, PhysicsBundle, Velocity};
-use crate::{GameState, GameplaySet, ZLayer};
+use crate::combat::Health;
+use crate::experience::Level;
+use crate::loading::{sprite_or_placeholder, TextureAssets};
+use crate::movement::{Mass, PhysicsBundle};
+use crate::pickup::PowerUps;
+use crate::run::{GameplayEntity, RunSetup};
+use crate::upgrade::StatStacks;
+use crate::weapon::{Arsenal, STARTING_WEAPONS};
+use crate::{GameState, ZLayer};
 use bevy::prelude::*;
-use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy::utils::info;
+use rand::Rng;
 
-pub const BULLET_RADIUS: f32 = 10.0;
+/// Size of the player's sprite before it's scaled up, the collider covers all of it
+pub const PLAYER_SIZE: Vec2 = Vec2::new(32.0, 32.0);
 
 pub struct PlayerPlugin;
 
 #[derive(Component)]
 pub struct Player;
 
+/// Player attributes that upgrades can improve over a run
+#[derive(Component)]
+pub struct PlayerStats {
+    pub move_speed: f32,
+    /// Pickups closer than this are pulled toward the player
+    pub pickup_radius: f32,
+    pub damage_multiplier: f32,
+    pub cooldown_multiplier: f32,
+    /// Chance for a hit to deal `crit_multiplier` times its damage
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            move_speed: 100.0,
+            pickup_radius: 150.0,
+            damage_multiplier: 1.0,
+            cooldown_multiplier: 1.0,
+            crit_chance: 0.1,
+            crit_multiplier: 2.0,
+        }
+    }
+}
+
+impl PlayerStats {
+    /// Damage of a single hit and whether it was a critical one
+    pub fn roll_damage(&self, damage: f32, rng: &mut impl Rng) -> (f32, bool) {
+        if rng.gen::<f32>() < self.crit_chance {
+            (damage * self.crit_multiplier, true)
+        } else {
+            (damage, false)
+        }
+    }
+}
+
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), spawn_player)
-            .add_systems(
-                Update,
-                shoot
-                    .in_set(GameplaySet::PlayerUpdate)
-                    .run_if(in_state(GameState::Playing)),
-            );
+        app.add_systems(OnEnter(GameState::Playing), spawn_player.in_set(RunSetup));
     }
 }
 
@@ -36,17 +72,10 @@ fn spawn_player(
     textures: Res<TextureAssets>,
     image_assets: Res<Assets<Image>>,
 ) {
-    let texture = textures.player.clone();
-    let Some(image_data) = image_assets.get(texture.clone()) else {
-        panic!("Failed to get image data for player spawn");
-    };
-    let size = Vec2::new(
-        image_data.texture_descriptor.size.width as f32,
-        image_data.texture_descriptor.size.height as f32,
-    );
-
+    let (texture, sprite) = sprite_or_placeholder(&textures.player, &image_assets, PLAYER_SIZE);
     let sprite = SpriteBundle {
         texture,
+        sprite,
         transform: Transform::from_translation(Vec3::new(0., 0., ZLayer::Character.into()))
             .with_scale(Vec3::new(3., 3., 3.)),
         ..Default::default()
@@ -59,75 +88,15 @@ fn spawn_player(
             mass: Mass(10.),
             ..default()
         })
-        .insert(Collider::new_aabb(CollisionLayer::Player, size / 2.0))
-        .insert(Weapon {
-            timer: Timer::new(Duration::from_millis(500), TimerMode::Once),
-            speed: 300.0,
-            damage: 50.0,
-        });
-}
-
-#[derive(Component)]
-#[component(storage = "SparseSet")]
-pub struct Bullet {
-    damage: f32,
-}
-
-#[derive(Component)]
-pub struct Weapon {
-    timer: Timer,
-    speed: f32,
-    damage: f32,
-}
-
-/*
-#[derive(Component)]
-#[component(storage = "SparseSet")]
-pub struct SpawnPosition(Vec3);
-
-#[derive(Component)]
-#[component(storage = "SparseSet")]
-pub struct LifeTime(Timer);
-*/
-
-fn shoot(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    actions: Res<Actions>,
-    mut player_query: Query<(&GlobalTransform, &mut Weapon), With<Player>>,
-    time: Res<Time>, 
-) {
-    let (player_transform, mut weapon) = player_query.single_mut();
-    weapon.timer.tick(time.delta());
-    
-    if let Some(shoot_coord) = actions.shoot {
-        if weapon.timer.finished() {
-            let direction_vec = (shoot_coord - player_transform.translation().truncate()).normalize();
-            let velocity_vec = direction_vec * weapon.speed;
-            let color = Color::hsl(0.5, 0.95, 0.7);
-            let handle = Mesh2dHandle(meshes.add(Circle::new(BULLET_RADIUS)));
-
-            // todo: precreate these resources
-            commands
-                .spawn(MaterialMesh2dBundle {
-                    mesh: handle,
-                    transform: Transform::from_translation(player_transform.translation()),
-                    material: materials.add(color),
-                    ..default()
-                })
-                .insert(Bullet { damage: weapon.damage })
-                .insert(PhysicsBundle {
-                    mass: Mass(10.),
-                    velocity: Velocity(velocity_vec),
-                    ..default()
-                })
-                .insert(Collider::new_circle(
-                    CollisionLayer::PlayerProjectile,
-                    BULLET_RADIUS,
-                ));
-            
-            weapon.timer.reset();
-        }
-    }
+        .insert(Collider::new_aabb(
+            CollisionLayer::Player,
+            PLAYER_SIZE / 2.0,
+        ))
+        .insert(Health::new(100.0))
+        .insert(Arsenal::new(&STARTING_WEAPONS))
+        .insert(PlayerStats::default())
+        .insert(StatStacks::default())
+        .insert(Level::default())
+        .insert(PowerUps::default())
+        .insert(GameplayEntity);
 }
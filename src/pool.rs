@@ -0,0 +1,120 @@
+use bevy::ecs::entity::EntityHashSet;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+/// Marks an entity that is parked in a [`Pool`] and not part of the simulation
+#[derive(Component)]
+pub struct Pooled;
+
+/// Free list of recycled entities for one kind of gameplay object
+///
+/// The bundle `B` holds the gameplay components of the object. Releasing an entity strips
+/// these and hides it, but keeps its render components (mesh, material, sprite) so that
+/// reacquiring it doesn't touch any assets.
+#[derive(Resource)]
+pub struct Pool<B: Bundle> {
+    /// Reused last in, first out, so the same releases always hand out the same entities
+    free: Vec<Entity>,
+    /// The same entities as `free`, to catch double releases without searching it
+    parked: EntityHashSet,
+    _marker: PhantomData<fn() -> B>,
+}
+
+impl<B: Bundle> Default for Pool<B> {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            parked: EntityHashSet::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<B: Bundle> Pool<B> {
+    /// Reuses a parked entity or spawns a new one using `visual` for its render components
+    pub fn acquire<V: Bundle>(
+        &mut self,
+        commands: &mut Commands,
+        transform: Transform,
+        bundle: B,
+        visual: impl FnOnce() -> V,
+    ) -> Entity {
+        while let Some(entity) = self.free.pop() {
+            self.parked.remove(&entity);
+            // the entity may have been despawned while parked
+            if let Some(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.remove::<Pooled>().insert((
+                    bundle,
+                    transform,
+                    Visibility::Inherited,
+                ));
+                return entity;
+            }
+        }
+
+        commands.spawn((visual(), bundle)).insert(transform).id()
+    }
+
+    /// Parks an entity; releasing the same entity twice is a no-op
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.parked.contains(&entity) {
+            return;
+        }
+
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands
+                .remove::<B>()
+                .insert((Visibility::Hidden, Pooled));
+            self.free.push(entity);
+            self.parked.insert(entity);
+        }
+    }
+
+    #[allow(unused)]
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::CommandQueue;
+
+    #[derive(Component)]
+    struct Marker;
+
+    /// Runs `f` with commands on `world` and applies them
+    fn with_commands<T>(world: &mut World, f: impl FnOnce(&mut Commands) -> T) -> T {
+        let mut queue = CommandQueue::default();
+        let result = f(&mut Commands::new(&mut queue, world));
+        queue.apply(world);
+        result
+    }
+
+    #[test]
+    fn released_entities_are_reused() {
+        let mut world = World::new();
+        let mut pool = Pool::<Marker>::default();
+
+        let first = with_commands(&mut world, |commands| {
+            pool.acquire(commands, Transform::default(), Marker, || ())
+        });
+        with_commands(&mut world, |commands| {
+            pool.release(commands, first);
+            pool.release(commands, first);
+        });
+        assert_eq!(pool.available(), 1);
+        assert!(world.get::<Pooled>(first).is_some());
+        assert!(world.get::<Marker>(first).is_none());
+
+        let second = with_commands(&mut world, |commands| {
+            pool.acquire(commands, Transform::default(), Marker, || ())
+        });
+        assert_eq!(second, first);
+        assert_eq!(world.entities().len(), 1);
+        assert_eq!(pool.available(), 0);
+        assert!(world.get::<Pooled>(second).is_none());
+        assert!(world.get::<Marker>(second).is_some());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::enemy::Enemy;
+use crate::map::{reflect_velocity, MapBoundary, MAP_RADIUS};
+use crate::movement::Velocity;
+use crate::player::Player;
+use crate::projectile::{Projectile, ProjectileSpawner, SpawnPosition};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Projectiles caught by the player within this distance are recycled
+const BOOMERANG_CATCH_DISTANCE: f32 = 30.0;
+/// Each jump of a chain deals this fraction of the previous jump's damage
+const CHAIN_FALLOFF: f32 = 0.8;
+const LIGHTNING_DURATION: Duration = Duration::from_millis(150);
+
+/// Describes a behaviour to attach to a projectile; any number of these can be combined
+#[derive(Clone, Copy)]
+pub enum ProjectileBehaviour {
+    Pierce(u32),
+    Homing { turn_rate: f32, range: f32 },
+    Boomerang { distance: f32 },
+    Ricochet(u32),
+    Split { count: u32, spread: f32 },
+    Chain { jumps: u32, range: f32 },
+}
+
+/// Lets a projectile pass through this many enemies before it is spent
+#[derive(Component)]
+pub struct Pierce {
+    pub remaining: u32,
+    pub hit: Vec<Entity>,
+}
+
+impl Pierce {
+    pub fn new(count: u32) -> Self {
+        Self {
+            remaining: count,
+            hit: Vec::new(),
+        }
+    }
+}
+
+/// Steers a projectile toward the nearest enemy within `range`
+#[derive(Component)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub range: f32,
+}
+
+/// Flies out `distance` from where it was fired, then turns back toward the player
+#[derive(Component)]
+pub struct Boomerang {
+    pub distance: f32,
+    pub returning: bool,
+}
+
+/// Bounces off the map boundary this many more times instead of expiring there
+#[derive(Component)]
+pub struct Ricochet {
+    pub remaining: u32,
+}
+
+/// Breaks into `count` weaker projectiles fanned over `spread` radians once spent
+#[derive(Component)]
+pub struct Split {
+    pub count: u32,
+    pub spread: f32,
+}
+
+/// Lightning jumps from the hit enemy to up to `jumps` more enemies, each within `range` of the last
+#[derive(Component)]
+pub struct Chain {
+    pub jumps: u32,
+    pub range: f32,
+}
+
+/// Optional projectile components, stripped when the projectile goes back to the pool
+pub type ProjectileBehaviours = (Pierce, Homing, Boomerang, Ricochet, Split, Chain);
+
+#[derive(Component)]
+pub struct LightningArc {
+    points: Vec<Vec2>,
+    timer: Timer,
+}
+
+impl LightningArc {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            timer: Timer::new(LIGHTNING_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+pub fn steer_homing_projectiles(
+    time: Res<Time>,
+    mut projectile_query: Query<(&Transform, &mut Velocity, &Homing), With<Projectile>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+) {
+    for (transform, mut velocity, homing) in projectile_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let Some(target) = nearest_enemy(position, homing.range, &enemy_query) else {
+            continue;
+        };
+        let turn = homing.turn_rate * time.delta_seconds();
+        velocity.0 = turn_toward(velocity.0, target - position, turn);
+    }
+}
+
+pub fn return_boomerangs(
+    mut projectiles: ProjectileSpawner,
+    time: Res<Time>,
+    mut projectile_query: Query<
+        (
+            Entity,
+            &Transform,
+            &SpawnPosition,
+            &mut Velocity,
+            &mut Boomerang,
+        ),
+        With<Projectile>,
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Projectile>)>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for (entity, transform, spawn_position, mut velocity, mut boomerang) in
+        projectile_query.iter_mut()
+    {
+        let position = transform.translation.truncate();
+        if !boomerang.returning {
+            boomerang.returning =
+                position.distance(spawn_position.0.truncate()) >= boomerang.distance;
+            continue;
+        }
+
+        if position.distance(player_position) <= BOOMERANG_CATCH_DISTANCE {
+            projectiles.recycle(entity);
+            continue;
+        }
+        // turn hard enough to come around in about half a second
+        let turn = std::f32::consts::TAU * time.delta_seconds();
+        velocity.0 = turn_toward(velocity.0, player_position - position, turn);
+    }
+}
+
+pub fn ricochet_projectiles(
+    mut projectile_query: Query<(&mut Transform, &mut Velocity, &mut Ricochet), With<Projectile>>,
+    boundary_query: Query<&Transform, (With<MapBoundary>, Without<Projectile>)>,
+) {
+    let Ok(boundary_transform) = boundary_query.get_single() else {
+        return;
+    };
+    let center = boundary_transform.translation.truncate();
+
+    for (mut transform, mut velocity, mut ricochet) in projectile_query.iter_mut() {
+        let offset = transform.translation.truncate() - center;
+        if ricochet.remaining == 0 || offset.length() <= MAP_RADIUS {
+            continue;
+        }
+
+        let normal = offset.normalize();
+        let inside = center + normal * (MAP_RADIUS - 1.0);
+        transform.translation.x = inside.x;
+        transform.translation.y = inside.y;
+        velocity.0 = reflect_velocity(velocity.0, normal);
+        ricochet.remaining -= 1;
+    }
+}
+
+pub fn draw_lightning_arcs(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    mut arc_query: Query<(Entity, &mut LightningArc)>,
+) {
+    for (entity, mut arc) in arc_query.iter_mut() {
+        arc.timer.tick(time.delta());
+        if arc.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = 1.0 - arc.timer.fraction();
+        gizmos.linestrip_2d(
+            arc.points.iter().copied(),
+            Color::rgba(0.6, 0.8, 1.0, alpha),
+        );
+    }
+}
+
+/// Picks the enemies a chain jumps to after hitting `first`, with the damage dealt to each
+pub fn chain_targets(
+    first: Entity,
+    damage: f32,
+    chain: &Chain,
+    enemy_query: &Query<(Entity, &Transform), With<Enemy>>,
+) -> Vec<(Entity, Vec2, f32)> {
+    let Ok((_, first_transform)) = enemy_query.get(first) else {
+        return Vec::new();
+    };
+
+    let mut visited = vec![first];
+    let mut targets = Vec::new();
+    let mut position = first_transform.translation.truncate();
+    let mut damage = damage;
+    for _ in 0..chain.jumps {
+        let next = enemy_query
+            .iter()
+            .filter(|(entity, _)| !visited.contains(entity))
+            .map(|(entity, transform)| (entity, transform.translation.truncate()))
+            .filter(|(_, enemy)| enemy.distance(position) <= chain.range)
+            .min_by(|(_, a), (_, b)| a.distance(position).total_cmp(&b.distance(position)));
+        let Some((entity, enemy)) = next else {
+            break;
+        };
+
+        damage *= CHAIN_FALLOFF;
+        visited.push(entity);
+        targets.push((entity, enemy, damage));
+        position = enemy;
+    }
+    targets
+}
+
+/// Directions for `count` projectiles fanned evenly over `spread` radians around `aim`
+pub fn fan_directions(aim: Vec2, count: u32, spread: f32) -> impl Iterator<Item = Vec2> {
+    (0..count).map(move |index| {
+        let offset = if count > 1 {
+            -spread / 2.0 + spread * index as f32 / (count - 1) as f32
+        } else {
+            0.0
+        };
+        Vec2::from_angle(offset).rotate(aim)
+    })
+}
+
+/// Position of the closest enemy to `position` that is at most `range` away
+pub fn nearest_enemy(
+    position: Vec2,
+    range: f32,
+    enemy_query: &Query<&Transform, With<Enemy>>,
+) -> Option<Vec2> {
+    enemy_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .filter(|enemy| enemy.distance(position) <= range)
+        .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+}
+
+/// Rotates `velocity` toward `desired` by at most `max_turn` radians, keeping its speed
+fn turn_toward(velocity: Vec2, desired: Vec2, max_turn: f32) -> Vec2 {
+    // atan2 rather than `angle_between`, which is NaN for zero vectors
+    let angle = velocity
+        .perp_dot(desired)
+        .atan2(velocity.dot(desired))
+        .clamp(-max_turn, max_turn);
+    Vec2::from_angle(angle).rotate(velocity)
+}
@@ -0,0 +1,298 @@
+use crate::collision::{Collider, CollisionEvent, CollisionLayer};
+use crate::combat::DamageEvent;
+use crate::enemy::Enemy;
+use crate::map::MAP_RADIUS;
+use crate::movement::{Mass, PhysicsBundle, Velocity};
+use crate::player::{Player, PlayerStats};
+use crate::pool::Pool;
+use crate::projectile::behaviour::{
+    chain_targets, draw_lightning_arcs, return_boomerangs, ricochet_projectiles,
+    steer_homing_projectiles, Boomerang, Chain, Homing, LightningArc, ProjectileBehaviours,
+    Ricochet, Split,
+};
+use crate::run::{GameRng, GameplayEntity};
+use crate::{GameState, GameplaySet};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use std::time::Duration;
+
+pub use crate::projectile::behaviour::{
+    fan_directions, nearest_enemy, Pierce, ProjectileBehaviour,
+};
+
+mod behaviour;
+
+pub const BULLET_RADIUS: f32 = 10.0;
+pub const PROJECTILE_LIFETIME: Duration = Duration::from_secs(4);
+pub const PROJECTILE_RANGE: f32 = 1500.0;
+
+pub struct ProjectilePlugin;
+
+/// This plugin owns every projectile in flight
+/// Projectiles are recycled through a [`Pool`] once they hit something, run out of time or
+/// leave their range, so long sessions don't keep allocating entities and assets
+/// Behaviours like homing or ricochet are plain components, see [`ProjectileBehaviour`]
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pool<ProjectileBundle>>()
+            .add_systems(Startup, setup_projectile_assets)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        steer_homing_projectiles,
+                        return_boomerangs,
+                        ricochet_projectiles,
+                        expire_projectiles,
+                    )
+                        .chain()
+                        .in_set(GameplaySet::PrePhysics),
+                    handle_projectile_hits.in_set(GameplaySet::Collisions),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                draw_lightning_arcs.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct ProjectileAssets {
+    pub bullet_mesh: Mesh2dHandle,
+    pub bullet_material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub damage: f32,
+    pub max_range: f32,
+}
+
+#[derive(Component)]
+pub struct SpawnPosition(pub Vec3);
+
+#[derive(Component)]
+pub struct LifeTime(pub Timer);
+
+#[derive(Bundle)]
+pub struct ProjectileBundle {
+    projectile: Projectile,
+    physics: PhysicsBundle,
+    collider: Collider,
+    spawn_position: SpawnPosition,
+    life_time: LifeTime,
+}
+
+/// Spawns projectiles out of the pool with the shared bullet mesh and material
+#[derive(SystemParam)]
+pub struct ProjectileSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    pool: ResMut<'w, Pool<ProjectileBundle>>,
+    assets: Res<'w, ProjectileAssets>,
+}
+
+impl<'w, 's> ProjectileSpawner<'w, 's> {
+    pub fn spawn(&mut self, position: Vec3, velocity: Vec2, damage: f32) -> Entity {
+        let bundle = ProjectileBundle {
+            projectile: Projectile {
+                damage,
+                max_range: PROJECTILE_RANGE,
+            },
+            physics: PhysicsBundle {
+                mass: Mass(10.),
+                velocity: Velocity(velocity),
+                ..default()
+            },
+            collider: Collider::new_circle(CollisionLayer::PlayerProjectile, BULLET_RADIUS),
+            spawn_position: SpawnPosition(position),
+            life_time: LifeTime(Timer::new(PROJECTILE_LIFETIME, TimerMode::Once)),
+        };
+
+        let assets = &self.assets;
+        self.pool.acquire(
+            &mut self.commands,
+            Transform::from_translation(position),
+            bundle,
+            || {
+                (
+                    MaterialMesh2dBundle {
+                        mesh: assets.bullet_mesh.clone(),
+                        material: assets.bullet_material.clone(),
+                        ..default()
+                    },
+                    GameplayEntity,
+                )
+            },
+        )
+    }
+
+    pub fn add_behaviour(&mut self, entity: Entity, behaviour: ProjectileBehaviour) {
+        let mut entity_commands = self.commands.entity(entity);
+        match behaviour {
+            ProjectileBehaviour::Pierce(count) => entity_commands.insert(Pierce::new(count)),
+            ProjectileBehaviour::Homing { turn_rate, range } => {
+                entity_commands.insert(Homing { turn_rate, range })
+            }
+            ProjectileBehaviour::Boomerang { distance } => entity_commands.insert(Boomerang {
+                distance,
+                returning: false,
+            }),
+            ProjectileBehaviour::Ricochet(bounces) => {
+                entity_commands.insert(Ricochet { remaining: bounces })
+            }
+            ProjectileBehaviour::Split { count, spread } => {
+                entity_commands.insert(Split { count, spread })
+            }
+            ProjectileBehaviour::Chain { jumps, range } => {
+                entity_commands.insert(Chain { jumps, range })
+            }
+        };
+    }
+
+    /// Sends a projectile back to the pool along with any behaviours it had
+    pub fn recycle(&mut self, entity: Entity) {
+        if let Some(mut entity_commands) = self.commands.get_entity(entity) {
+            entity_commands.remove::<ProjectileBehaviours>();
+        }
+        self.pool.release(&mut self.commands, entity);
+    }
+
+    pub fn commands(&mut self) -> &mut Commands<'w, 's> {
+        &mut self.commands
+    }
+}
+
+fn setup_projectile_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(ProjectileAssets {
+        bullet_mesh: Mesh2dHandle(meshes.add(Circle::new(BULLET_RADIUS))),
+        bullet_material: materials.add(Color::hsl(0.5, 0.95, 0.7)),
+    });
+}
+
+fn expire_projectiles(
+    mut projectiles: ProjectileSpawner,
+    time: Res<Time>,
+    mut projectile_query: Query<(
+        Entity,
+        &Projectile,
+        &Transform,
+        &SpawnPosition,
+        &mut LifeTime,
+    )>,
+) {
+    for (entity, projectile, transform, spawn_position, mut life_time) in
+        projectile_query.iter_mut()
+    {
+        life_time.0.tick(time.delta());
+        let travelled = transform.translation.distance(spawn_position.0);
+        let outside_map = transform.translation.truncate().length() > MAP_RADIUS;
+
+        if life_time.0.finished() || travelled > projectile.max_range || outside_map {
+            projectiles.recycle(entity);
+        }
+    }
+}
+
+fn handle_projectile_hits(
+    mut projectiles: ProjectileSpawner,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut projectile_query: Query<(
+        &Projectile,
+        &Transform,
+        &Velocity,
+        Option<&mut Pierce>,
+        Option<&Split>,
+        Option<&Chain>,
+    )>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    player_query: Query<&PlayerStats, With<Player>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok(stats) = player_query.get_single() else {
+        return;
+    };
+
+    // a projectile overlapping several enemies can show up in more than one event
+    let mut spent = Vec::new();
+    for event in collision_events.read() {
+        let (projectile_entity, target) = if projectile_query.contains(event.entity1) {
+            (event.entity1, event.entity2)
+        } else if projectile_query.contains(event.entity2) {
+            (event.entity2, event.entity1)
+        } else {
+            continue;
+        };
+
+        if !enemy_query.contains(target) || spent.contains(&projectile_entity) {
+            continue;
+        }
+
+        let (projectile, transform, velocity, mut pierce, split, chain) =
+            projectile_query.get_mut(projectile_entity).unwrap();
+        if pierce
+            .as_ref()
+            .is_some_and(|pierce| pierce.hit.contains(&target))
+        {
+            continue;
+        }
+
+        let (amount, critical) = stats.roll_damage(projectile.damage, &mut **rng);
+        damage_events.send(DamageEvent {
+            target,
+            amount,
+            critical,
+        });
+
+        let position = transform.translation;
+        if let Some(chain) = chain {
+            let mut points = vec![position.truncate()];
+            for (enemy, enemy_position, damage) in
+                chain_targets(target, projectile.damage, chain, &enemy_query)
+            {
+                damage_events.send(DamageEvent {
+                    target: enemy,
+                    amount: damage,
+                    critical: false,
+                });
+                points.push(enemy_position);
+            }
+            if points.len() > 1 {
+                projectiles
+                    .commands()
+                    .spawn((LightningArc::new(points), GameplayEntity));
+            }
+        }
+
+        if let Some(pierce) = pierce.as_mut() {
+            pierce.hit.push(target);
+            if pierce.remaining > 0 {
+                pierce.remaining -= 1;
+                continue;
+            }
+        }
+
+        spent.push(projectile_entity);
+        if let Some(split) = split {
+            let damage = projectile.damage / 2.0;
+            let speed = velocity.0.length();
+            let aim = velocity.0.normalize_or_zero();
+            for direction in fan_directions(aim, split.count, split.spread) {
+                let fragment = projectiles.spawn(position, direction * speed, damage);
+                // don't let the fragments hit the enemy that split them right away
+                projectiles.commands().entity(fragment).insert(Pierce {
+                    remaining: 0,
+                    hit: vec![target],
+                });
+            }
+        }
+        projectiles.recycle(projectile_entity);
+    }
+}
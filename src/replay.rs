@@ -0,0 +1,308 @@
+use crate::actions::{update_fire_state, Actions};
+use crate::menu::MenuNavigation;
+use crate::run::{start_run, GameRng, RunSetup};
+use crate::settings::Settings;
+use crate::storage;
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const REPLAY_KEY: &str = "last_replay";
+
+pub struct ReplayPlugin;
+
+/// This plugin records the player's actions during a run and plays them back from the run's seed
+/// Runs are only recorded with `Settings::record_runs`, the last one is saved and can be watched from the menu
+/// Gameplay advances on a fixed tick, so the actions are recorded and played back once per tick
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LastReplay(storage::load(REPLAY_KEY)))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                start_replay.after(start_run).in_set(RunSetup),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    feed_replay.before(update_fire_state),
+                    record_actions.after(update_fire_state),
+                )
+                    .in_set(GameplaySet::InputHandling),
+            )
+            .add_systems(
+                Update,
+                watch_replay
+                    .after(MenuNavigation)
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), save_recording)
+            .add_systems(OnEnter(GameState::Menu), (save_recording, stop_replay));
+    }
+}
+
+/// Everything needed to play a run again: its seed and what the player did every tick
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Replay {
+    seed: u64,
+    /// How many ticks in a row had the same actions, most ticks don't differ from the one before
+    frames: Vec<(u32, Actions)>,
+    /// Cards picked on level up, in order
+    upgrade_picks: Vec<usize>,
+}
+
+impl Replay {
+    /// Adds the actions of one more tick
+    fn record(&mut self, actions: Actions) {
+        match self.frames.last_mut() {
+            Some((count, last)) if *last == actions && *count < u32::MAX => *count += 1,
+            _ => self.frames.push((1, actions)),
+        }
+    }
+}
+
+/// The last recorded run, if there is one
+#[derive(Resource)]
+pub struct LastReplay(pub Option<Replay>);
+
+/// Records the current run
+#[derive(Resource)]
+pub struct Recorder(Replay);
+
+impl Recorder {
+    pub fn record_pick(&mut self, index: usize) {
+        self.0.upgrade_picks.push(index);
+    }
+}
+
+/// Replayed by the next run that starts
+#[derive(Resource)]
+struct PendingReplay(Replay);
+
+/// Plays a recorded run back instead of listening to input
+#[derive(Resource)]
+pub struct Playback {
+    replay: Replay,
+    frame: usize,
+    /// Ticks already played from the current entry in `replay.frames`
+    repeat: u32,
+    pick: usize,
+}
+
+impl Playback {
+    fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            frame: 0,
+            repeat: 0,
+            pick: 0,
+        }
+    }
+
+    fn next_frame(&mut self) -> Option<&Actions> {
+        let (count, _) = self.replay.frames.get(self.frame)?;
+        if self.repeat == *count {
+            self.frame += 1;
+            self.repeat = 0;
+        }
+        self.repeat += 1;
+        self.replay
+            .frames
+            .get(self.frame)
+            .map(|(_, actions)| actions)
+    }
+
+    pub fn next_pick(&mut self) -> Option<usize> {
+        let pick = self.replay.upgrade_picks.get(self.pick).copied();
+        self.pick += 1;
+        pick
+    }
+}
+
+/// Buttons with this start the [`LastReplay`]
+#[derive(Component)]
+pub struct WatchReplay;
+
+pub fn not_replaying(playback: Option<Res<Playback>>) -> bool {
+    playback.is_none()
+}
+
+fn watch_replay(
+    mut commands: Commands,
+    last_replay: Res<LastReplay>,
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<WatchReplay>)>,
+) {
+    let Some(replay) = &last_replay.0 else {
+        return;
+    };
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        commands.insert_resource(PendingReplay(replay.clone()));
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Reseeds a run that's replayed or recorded
+fn start_replay(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    pending: Option<Res<PendingReplay>>,
+) {
+    // the replay stays around after it ended so its game over isn't taken for a real run
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<Recorder>();
+    if let Some(pending) = pending {
+        let replay = pending.0.clone();
+        commands.insert_resource(GameRng::seeded(replay.seed));
+        commands.insert_resource(Playback::new(replay));
+        commands.remove_resource::<PendingReplay>();
+    } else if settings.record_runs {
+        let seed = rand::random();
+        commands.insert_resource(GameRng::seeded(seed));
+        commands.insert_resource(Recorder(Replay { seed, ..default() }));
+    }
+}
+
+fn feed_replay(
+    mut actions: ResMut<Actions>,
+    playback: Option<ResMut<Playback>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    match playback.next_frame() {
+        Some(recorded) => actions.replay(recorded),
+        None => {
+            info!("Replay finished");
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+fn record_actions(actions: Res<Actions>, recorder: Option<ResMut<Recorder>>) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    recorder.0.record(actions.recorded());
+}
+
+fn save_recording(
+    mut commands: Commands,
+    recorder: Option<Res<Recorder>>,
+    mut last_replay: ResMut<LastReplay>,
+) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    storage::save_compact(REPLAY_KEY, &recorder.0);
+    last_replay.0 = Some(recorder.0.clone());
+    commands.remove_resource::<Recorder>();
+}
+
+fn stop_replay(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::Enemy;
+    use crate::testing::{Harness, SEED};
+
+    fn moving(direction: Vec2) -> Actions {
+        Actions {
+            player_movement: Some(direction),
+            ..default()
+        }
+    }
+
+    fn replay(frames: Vec<(u32, Actions)>) -> Replay {
+        Replay {
+            seed: SEED,
+            frames,
+            upgrade_picks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn repeated_actions_are_counted_instead_of_stored_again() {
+        let mut replay = replay(Vec::new());
+        replay.record(moving(Vec2::X));
+        replay.record(moving(Vec2::X));
+        replay.record(moving(Vec2::Y));
+        replay.record(moving(Vec2::X));
+        assert_eq!(
+            replay.frames,
+            vec![
+                (2, moving(Vec2::X)),
+                (1, moving(Vec2::Y)),
+                (1, moving(Vec2::X)),
+            ]
+        );
+    }
+
+    #[test]
+    fn full_counts_start_a_new_entry() {
+        let mut replay = replay(vec![(u32::MAX, moving(Vec2::X))]);
+        replay.record(moving(Vec2::X));
+        assert_eq!(
+            replay.frames,
+            vec![(u32::MAX, moving(Vec2::X)), (1, moving(Vec2::X))]
+        );
+    }
+
+    #[test]
+    fn playback_repeats_every_entry_as_often_as_it_was_counted() {
+        let mut playback = Playback::new(replay(vec![(2, moving(Vec2::X)), (1, moving(Vec2::Y))]));
+        assert_eq!(playback.next_frame(), Some(&moving(Vec2::X)));
+        assert_eq!(playback.next_frame(), Some(&moving(Vec2::X)));
+        assert_eq!(playback.next_frame(), Some(&moving(Vec2::Y)));
+        assert_eq!(playback.next_frame(), None);
+
+        let mut empty = Playback::new(replay(Vec::new()));
+        assert_eq!(empty.next_frame(), None);
+    }
+
+    fn positions(harness: &mut Harness) -> (Vec3, Vec<Vec3>) {
+        let player = harness.get::<Transform>(harness.player).translation;
+        let enemies = harness
+            .query::<&Transform, With<Enemy>>()
+            .iter()
+            .map(|transform| transform.translation)
+            .collect();
+        (player, enemies)
+    }
+
+    #[test]
+    fn replays_play_out_like_the_recorded_run() {
+        let with_replays = |mut harness: Harness| {
+            harness.app.add_systems(
+                FixedUpdate,
+                (feed_replay, record_actions)
+                    .chain()
+                    .in_set(GameplaySet::InputHandling),
+            );
+            harness
+        };
+
+        let mut recorded = with_replays(Harness::new().without_gravity());
+        recorded.app.insert_resource(Recorder(replay(Vec::new())));
+        for (ticks, direction) in [(90, Some(Vec2::X)), (60, Some(Vec2::NEG_Y)), (90, None)] {
+            recorded.actions().player_movement = direction;
+            recorded.step(ticks);
+        }
+        let replay = recorded.app.world.resource::<Recorder>().0.clone();
+
+        let mut replayed = with_replays(Harness::new().without_gravity());
+        replayed.app.insert_resource(Playback::new(replay));
+        replayed.step(240);
+
+        let (player, enemies) = positions(&mut replayed);
+        assert!(player.x > 100.0, "{player}");
+        assert!(!enemies.is_empty());
+        assert_eq!((player, enemies), positions(&mut recorded));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::combat::DeathEvent;
+use crate::enemy::EnemyDied;
+use crate::player::Player;
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Duration;
+
+pub struct RunPlugin;
+
+/// This plugin tracks the current run and tears it down once it's over
+/// Systems that set up a run go in [`RunSetup`], entities that belong to a run get a [`GameplayEntity`]
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(OnEnter(GameState::Playing), RunSetup.run_if(needs_new_run))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    teardown_run.before(RunSetup).run_if(needs_new_run),
+                    start_run.in_set(RunSetup),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Menu), teardown_run)
+            .add_systems(
+                FixedUpdate,
+                (
+                    track_run.in_set(GameplaySet::PlayerUpdate),
+                    end_run_on_player_death.in_set(GameplaySet::Deaths),
+                    count_kills.in_set(GameplaySet::Rewards),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Systems spawning everything a run needs
+/// They only run when entering `GameState::Playing` starts a new run, not when it resumes one
+#[derive(SystemSet, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct RunSetup;
+
+/// Everything with this marker is despawned when the run ends
+#[derive(Component)]
+pub struct GameplayEntity;
+
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub time: Duration,
+    pub kills: u32,
+    pub score: u32,
+    /// Set once the player died, the next time Playing is entered starts over
+    pub over: bool,
+}
+
+/// Randomness that changes how a run plays out comes from here, so a run can be replayed from its seed
+/// Purely cosmetic randomness can keep using `rand::thread_rng`
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(StdRng);
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+fn needs_new_run(stats: Option<Res<RunStats>>) -> bool {
+    stats.is_none_or(|stats| stats.over)
+}
+
+pub fn start_run(mut commands: Commands) {
+    commands.insert_resource(RunStats::default());
+    commands.insert_resource(GameRng::seeded(rand::random()));
+}
+
+fn teardown_run(mut commands: Commands, entities: Query<Entity, With<GameplayEntity>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<RunStats>();
+}
+
+fn track_run(time: Res<Time>, mut stats: ResMut<RunStats>) {
+    stats.time += time.delta();
+}
+
+fn count_kills(mut enemy_died_events: EventReader<EnemyDied>, mut stats: ResMut<RunStats>) {
+    for _ in enemy_died_events.read() {
+        stats.kills += 1;
+    }
+}
+
+fn end_run_on_player_death(
+    mut death_events: EventReader<DeathEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut stats: ResMut<RunStats>,
+    player_query: Query<(), With<Player>>,
+) {
+    if death_events
+        .read()
+        .any(|event| player_query.contains(event.entity))
+    {
+        stats.over = true;
+        next_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::enemy::EnemyDied;
+use crate::experience::Level;
+use crate::player::Player;
+use crate::replay::not_replaying;
+use crate::run::{RunSetup, RunStats};
+use crate::storage;
+use crate::wave::{Wave, WaveCompleted};
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Kills closer together than this keep the combo going
+const COMBO_WINDOW: Duration = Duration::from_secs(2);
+/// Each kill in a combo adds this much to the multiplier
+const COMBO_STEP: f32 = 0.1;
+const MAX_COMBO_MULTIPLIER: f32 = 5.0;
+const WAVE_BONUS: u32 = 100;
+const SURVIVAL_POINTS_PER_SECOND: u32 = 1;
+const HIGH_SCORE_COUNT: usize = 10;
+const HIGH_SCORE_KEY: &str = "high_scores";
+
+pub struct ScorePlugin;
+
+/// This plugin scores the run and keeps a table of the best runs across sessions
+/// Kills score more while a combo is going, surviving waves and time adds bonuses on top
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<HighScores>(HIGH_SCORE_KEY).unwrap_or_default())
+            .add_systems(OnEnter(GameState::Playing), start_scoring.in_set(RunSetup))
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                record_high_score.run_if(not_replaying),
+            )
+            .add_systems(
+                FixedUpdate,
+                (score_kills, score_waves, score_survival, tick_combo)
+                    .chain()
+                    .in_set(GameplaySet::Rewards)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Combo {
+    pub kills: u32,
+    timer: Timer,
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> f32 {
+        (1.0 + self.kills.saturating_sub(1) as f32 * COMBO_STEP).min(MAX_COMBO_MULTIPLIER)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScore {
+    pub score: u32,
+    pub time_secs: u64,
+    pub kills: u32,
+    pub wave: u32,
+    pub level: u32,
+}
+
+/// The best runs so far, highest score first
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores(pub Vec<HighScore>);
+
+impl HighScores {
+    /// Adds the run if it makes the table and returns its rank
+    fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.0.partition_point(|other| other.score >= entry.score);
+        if rank >= HIGH_SCORE_COUNT {
+            return None;
+        }
+        self.0.insert(rank, entry);
+        self.0.truncate(HIGH_SCORE_COUNT);
+        Some(rank)
+    }
+}
+
+/// Survival points are paid out per whole second
+#[derive(Resource)]
+struct SurvivalTimer(Timer);
+
+fn start_scoring(mut commands: Commands) {
+    commands.insert_resource(Combo::default());
+    commands.insert_resource(SurvivalTimer(Timer::from_seconds(
+        1.0,
+        TimerMode::Repeating,
+    )));
+}
+
+fn score_kills(
+    mut enemy_died_events: EventReader<EnemyDied>,
+    mut stats: ResMut<RunStats>,
+    mut combo: ResMut<Combo>,
+) {
+    for event in enemy_died_events.read() {
+        combo.kills += 1;
+        combo.timer = Timer::new(COMBO_WINDOW, TimerMode::Once);
+        stats.score += (event.kind.score() as f32 * combo.multiplier()).round() as u32;
+    }
+}
+
+fn score_waves(mut wave_completed_events: EventReader<WaveCompleted>, mut stats: ResMut<RunStats>) {
+    for event in wave_completed_events.read() {
+        stats.score += WAVE_BONUS * event.number;
+    }
+}
+
+fn score_survival(time: Res<Time>, mut timer: ResMut<SurvivalTimer>, mut stats: ResMut<RunStats>) {
+    timer.0.tick(time.delta());
+    stats.score += SURVIVAL_POINTS_PER_SECOND * timer.0.times_finished_this_tick();
+}
+
+fn tick_combo(time: Res<Time>, mut combo: ResMut<Combo>) {
+    combo.timer.tick(time.delta());
+    if combo.timer.just_finished() {
+        combo.kills = 0;
+    }
+}
+
+fn record_high_score(
+    stats: Res<RunStats>,
+    wave: Res<Wave>,
+    mut high_scores: ResMut<HighScores>,
+    player_query: Query<&Level, With<Player>>,
+) {
+    let entry = HighScore {
+        score: stats.score,
+        time_secs: stats.time.as_secs(),
+        kills: stats.kills,
+        wave: wave.number,
+        level: player_query.get_single().map_or(1, |level| level.level),
+    };
+    if let Some(rank) = high_scores.insert(entry) {
+        info!("new high score at rank {}", rank + 1);
+        storage::save(HIGH_SCORE_KEY, &*high_scores);
+    }
+}
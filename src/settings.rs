@@ -0,0 +1,454 @@
+use crate::actions::{spawn_controls_section, AimSettings, InputMap, TouchScheme, TouchSettings};
+use crate::menu::{spawn_button, text_style, MenuNavigation};
+use crate::storage;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = "settings";
+const VOLUME_STEP: f32 = 0.1;
+const SCREEN_SHAKE_STEP: f32 = 0.25;
+const MAX_SCREEN_SHAKE: f32 = 2.0;
+const STICK_SIZE_STEP: f32 = 10.0;
+const STICK_SIZE_RANGE: (f32, f32) = (40.0, 160.0);
+const STICK_OPACITY_STEP: f32 = 0.1;
+const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1600, 900),
+    (1600, 1200),
+    (1920, 1080),
+    (2560, 1440),
+];
+const WINDOW_MODES: [WindowMode; 3] = [
+    WindowMode::Windowed,
+    WindowMode::BorderlessFullscreen,
+    WindowMode::Fullscreen,
+];
+
+pub struct SettingsPlugin;
+
+/// This plugin holds the player's preferences and keeps them in a config file, or browser storage on the web
+/// The settings panel is shared between the main menu and the pause menu
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // the desktop build loads the settings before this to open its window with them
+        if !app.world.contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_systems(
+            Update,
+            (
+                toggle_settings_panel,
+                click_setting_buttons,
+                update_setting_labels,
+                save_settings.run_if(resource_changed::<Settings>),
+            )
+                .chain()
+                .after(MenuNavigation),
+        );
+        // phones always run fullscreen at their native resolution
+        if !cfg!(any(target_os = "android", target_os = "ios")) {
+            app.add_systems(
+                Update,
+                apply_window_settings.run_if(resource_changed::<Settings>),
+            );
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Multiplies the volume of every sound, from 0 to 1
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub window_mode: WindowMode,
+    /// Window size in windowed mode
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    /// Scales camera shake, 0 turns it off
+    pub screen_shake: f32,
+    pub input_map: InputMap,
+    pub touch: TouchSettings,
+    pub aim: AimSettings,
+    /// Records every run so the last one can be watched again from the menu
+    pub record_runs: bool,
+    #[serde(skip)]
+    pub show_hitboxes: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 1.0,
+            window_mode: WindowMode::Windowed,
+            resolution: (1600, 1200),
+            vsync: true,
+            screen_shake: 1.0,
+            input_map: InputMap::default(),
+            touch: TouchSettings::default(),
+            aim: AimSettings::default(),
+            record_runs: false,
+            show_hitboxes: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl Settings {
+    /// The saved settings, or the defaults if nothing was saved yet
+    pub fn load() -> Self {
+        let settings: Self = storage::load(SETTINGS_KEY).unwrap_or_default();
+        for (binding, controls) in settings.input_map.conflicts() {
+            warn!("{binding:?} is bound to several controls: {controls:?}");
+        }
+        settings
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    fn volume_mut(&mut self, volume: Volume) -> &mut f32 {
+        match volume {
+            Volume::Master => &mut self.master_volume,
+            Volume::Music => &mut self.music_volume,
+            Volume::Sfx => &mut self.sfx_volume,
+        }
+    }
+}
+
+/// Buttons with this show or hide every [`SettingsPanel`]
+#[derive(Component)]
+pub struct ToggleSettings;
+
+/// Starts out hidden, see [`spawn_settings_panel`]
+#[derive(Component)]
+pub struct SettingsPanel;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Volume {
+    Master,
+    Music,
+    Sfx,
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingButton {
+    ChangeVolume(Volume, f32),
+    ChangeScreenShake(f32),
+    CycleWindowMode,
+    CycleResolution,
+    ToggleVsync,
+    CycleAimMode,
+    ToggleHitboxes,
+    ToggleRecording,
+    CycleTouchScheme,
+    ChangeStickSize(f32),
+    ChangeStickOpacity(f32),
+}
+
+#[derive(Component)]
+enum SettingLabel {
+    Volume(Volume),
+    WindowMode,
+    Resolution,
+    Vsync,
+    ScreenShake,
+    AimMode,
+    Hitboxes,
+    Recording,
+    TouchScheme,
+    StickSize,
+    StickOpacity,
+}
+
+fn spawn_setting_row(
+    parent: &mut ChildBuilder,
+    label: SettingLabel,
+    buttons: &[(&str, SettingButton)],
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                TextBundle::from_section("", text_style(25.0)).with_style(Style {
+                    width: Val::Px(260.0),
+                    ..default()
+                }),
+                label,
+            ));
+            for (text, button) in buttons {
+                spawn_button(row, text, 100.0, *button);
+            }
+        });
+}
+
+pub fn spawn_settings_panel(parent: &mut ChildBuilder) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(40.0),
+                    row_gap: Val::Px(20.0),
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsPanel,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Start,
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|general| {
+                    for volume in [Volume::Master, Volume::Music, Volume::Sfx] {
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::Volume(volume),
+                            &[
+                                ("-", SettingButton::ChangeVolume(volume, -VOLUME_STEP)),
+                                ("+", SettingButton::ChangeVolume(volume, VOLUME_STEP)),
+                            ],
+                        );
+                    }
+                    spawn_setting_row(
+                        general,
+                        SettingLabel::ScreenShake,
+                        &[
+                            ("-", SettingButton::ChangeScreenShake(-SCREEN_SHAKE_STEP)),
+                            ("+", SettingButton::ChangeScreenShake(SCREEN_SHAKE_STEP)),
+                        ],
+                    );
+                    if cfg!(any(target_os = "android", target_os = "ios")) {
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::TouchScheme,
+                            &[("Change", SettingButton::CycleTouchScheme)],
+                        );
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::StickSize,
+                            &[
+                                ("-", SettingButton::ChangeStickSize(-STICK_SIZE_STEP)),
+                                ("+", SettingButton::ChangeStickSize(STICK_SIZE_STEP)),
+                            ],
+                        );
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::StickOpacity,
+                            &[
+                                ("-", SettingButton::ChangeStickOpacity(-STICK_OPACITY_STEP)),
+                                ("+", SettingButton::ChangeStickOpacity(STICK_OPACITY_STEP)),
+                            ],
+                        );
+                    } else {
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::WindowMode,
+                            &[("Change", SettingButton::CycleWindowMode)],
+                        );
+                        spawn_setting_row(
+                            general,
+                            SettingLabel::Resolution,
+                            &[("Change", SettingButton::CycleResolution)],
+                        );
+                    }
+                    spawn_setting_row(
+                        general,
+                        SettingLabel::Vsync,
+                        &[("Toggle", SettingButton::ToggleVsync)],
+                    );
+                    spawn_setting_row(
+                        general,
+                        SettingLabel::AimMode,
+                        &[("Change", SettingButton::CycleAimMode)],
+                    );
+                    spawn_setting_row(
+                        general,
+                        SettingLabel::Hitboxes,
+                        &[("Toggle", SettingButton::ToggleHitboxes)],
+                    );
+                    spawn_setting_row(
+                        general,
+                        SettingLabel::Recording,
+                        &[("Toggle", SettingButton::ToggleRecording)],
+                    );
+                });
+            spawn_controls_section(panel);
+        });
+}
+
+fn toggle_settings_panel(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ToggleSettings>)>,
+    mut panel_query: Query<&mut Style, With<SettingsPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        for mut style in panel_query.iter_mut() {
+            style.display = match style.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+}
+
+fn next_in<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+    let index = options.iter().position(|option| *option == current);
+    options[index.map_or(0, |index| (index + 1) % options.len())]
+}
+
+fn click_setting_buttons(
+    mut settings: ResMut<Settings>,
+    interaction_query: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            SettingButton::ChangeVolume(volume, step) => {
+                let volume = settings.volume_mut(volume);
+                *volume = (*volume + step).clamp(0.0, 1.0);
+            }
+            SettingButton::ChangeScreenShake(step) => {
+                settings.screen_shake = (settings.screen_shake + step).clamp(0.0, MAX_SCREEN_SHAKE);
+            }
+            SettingButton::CycleWindowMode => {
+                settings.window_mode = next_in(&WINDOW_MODES, settings.window_mode);
+            }
+            SettingButton::CycleResolution => {
+                settings.resolution = next_in(&RESOLUTIONS, settings.resolution);
+            }
+            SettingButton::ToggleVsync => settings.vsync = !settings.vsync,
+            SettingButton::CycleAimMode => settings.aim.mode = settings.aim.mode.next(),
+            SettingButton::ToggleHitboxes => settings.show_hitboxes = !settings.show_hitboxes,
+            SettingButton::ToggleRecording => settings.record_runs = !settings.record_runs,
+            SettingButton::CycleTouchScheme => {
+                settings.touch.scheme = match settings.touch.scheme {
+                    TouchScheme::FollowFinger => TouchScheme::TwinStick,
+                    TouchScheme::TwinStick => TouchScheme::FollowFinger,
+                };
+            }
+            SettingButton::ChangeStickSize(step) => {
+                let (min, max) = STICK_SIZE_RANGE;
+                settings.touch.stick_radius = (settings.touch.stick_radius + step).clamp(min, max);
+            }
+            SettingButton::ChangeStickOpacity(step) => {
+                settings.touch.stick_opacity =
+                    (settings.touch.stick_opacity + step).clamp(0.1, 1.0);
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn update_setting_labels(
+    settings: Res<Settings>,
+    mut label_query: Query<(&mut Text, &SettingLabel)>,
+) {
+    for (mut text, label) in label_query.iter_mut() {
+        text.sections[0].value = match label {
+            SettingLabel::Volume(volume) => {
+                let (name, value) = match volume {
+                    Volume::Master => ("Volume", settings.master_volume),
+                    Volume::Music => ("Music", settings.music_volume),
+                    Volume::Sfx => ("Effects", settings.sfx_volume),
+                };
+                format!("{name} {:.0}%", value * 100.0)
+            }
+            SettingLabel::WindowMode => match settings.window_mode {
+                WindowMode::Windowed => "Windowed".to_string(),
+                WindowMode::Fullscreen => "Fullscreen".to_string(),
+                _ => "Borderless".to_string(),
+            },
+            SettingLabel::Resolution => {
+                format!("Size {}x{}", settings.resolution.0, settings.resolution.1)
+            }
+            SettingLabel::Vsync => format!("VSync {}", on_off(settings.vsync)),
+            SettingLabel::ScreenShake => {
+                format!("Screen shake {:.0}%", settings.screen_shake * 100.0)
+            }
+            SettingLabel::AimMode => format!("Aim {:?}", settings.aim.mode),
+            SettingLabel::Hitboxes => format!("Hitboxes {}", on_off(settings.show_hitboxes)),
+            SettingLabel::Recording => format!("Record runs {}", on_off(settings.record_runs)),
+            SettingLabel::TouchScheme => match settings.touch.scheme {
+                TouchScheme::FollowFinger => "Touch: follow finger".to_string(),
+                TouchScheme::TwinStick => "Touch: twin-stick".to_string(),
+            },
+            SettingLabel::StickSize => format!("Stick size {:.0}", settings.touch.stick_radius),
+            SettingLabel::StickOpacity => {
+                format!("Stick opacity {:.0}%", settings.touch.stick_opacity * 100.0)
+            }
+        };
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    storage::save(SETTINGS_KEY, &*settings);
+}
+
+/// Only touches the window when one of its settings changed, so a volume change doesn't undo a manual resize
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<(WindowMode, (u32, u32), bool)>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let wanted = (settings.window_mode, settings.resolution, settings.vsync);
+    if *applied == Some(wanted) {
+        return;
+    }
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+
+    window.mode = settings.window_mode;
+    window.present_mode = settings.present_mode();
+    let (width, height) = settings.resolution;
+    window.resolution.set(width as f32, height as f32);
+    *applied = Some(wanted);
+}
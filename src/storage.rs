@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Loads a value saved under `key` with [`save`], if there is one and it still parses
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let text = read(key)?;
+    match ron::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Failed to parse saved {key}: {error}");
+            None
+        }
+    }
+}
+
+/// Saves a value to a RON file in the user's data directory on desktop, or to `localStorage` in the browser
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(text) => write(key, &text),
+        Err(error) => warn!("Failed to serialize {key}: {error}"),
+    }
+}
+
+/// Like [`save`] but without any whitespace, for values that get big
+pub fn save_compact<T: Serialize>(key: &str, value: &T) {
+    match ron::to_string(value) {
+        Ok(text) => write(key, &text),
+        Err(error) => warn!("Failed to serialize {key}: {error}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+        })?;
+    Some(data_dir.join("wave_guy").join(format!("{key}.ron")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, text: &str) {
+    let Some(path) = path(key) else {
+        warn!("No data directory to save {key} to");
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, text));
+    if let Err(error) = result {
+        warn!("Failed to save {key} to {}: {error}", path.display());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("wave_guy.{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, text: &str) {
+    let saved = local_storage()
+        .is_some_and(|storage| storage.set_item(&format!("wave_guy.{key}"), text).is_ok());
+    if !saved {
+        warn!("Failed to save {key} to local storage");
+    }
+}
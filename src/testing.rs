@@ -0,0 +1,135 @@
+//! A headless app for gameplay tests, without a window, renderer, audio, interpolation or loaded assets
+
+use crate::actions::Actions;
+use crate::collision::{CollisionEvent, CollisionPlugin};
+use crate::combat::CombatPlugin;
+use crate::enemy::EnemyPlugin;
+use crate::gravity::{GravityPlugin, GravitySource};
+use crate::loading::TextureAssets;
+use crate::map::MapPlugin;
+use crate::movement::{Force, MovementPlugin};
+use crate::player::{Player, PlayerPlugin};
+use crate::run::GameRng;
+use crate::settings::Settings;
+use crate::wave::WavePlugin;
+use crate::{configure_gameplay_sets, GameState};
+use bevy::ecs::query::{QueryData, QueryFilter, ROQueryItem};
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+pub const SEED: u64 = 42;
+
+/// A run in progress, advanced one fixed step at a time
+/// None of the textures are loaded, so everything is drawn with placeholders; the player only moves through [`Actions`]
+pub struct Harness {
+    pub app: App,
+    pub player: Entity,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_state::<GameState>()
+            .insert_resource(Settings {
+                show_hitboxes: false,
+                ..default()
+            })
+            .init_resource::<Actions>()
+            .insert_resource(GameRng::seeded(SEED))
+            .insert_resource(unloaded_textures())
+            .add_plugins((
+                PlayerPlugin,
+                MovementPlugin,
+                CollisionPlugin,
+                CombatPlugin,
+                EnemyPlugin,
+                MapPlugin,
+                GravityPlugin,
+                WavePlugin,
+            ));
+        configure_gameplay_sets(&mut app);
+        // every update advances time by exactly one tick
+        let tick = app.world.resource::<Time<Fixed>>().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+        app.finish();
+        app.cleanup();
+
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        app.update();
+
+        let player = app
+            .world
+            .query_filtered::<Entity, With<Player>>()
+            .single(&app.world);
+        Self { app, player }
+    }
+
+    /// Runs `ticks` gameplay ticks, one per frame
+    pub fn step(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// What the player does on the next ticks, stays the same until changed
+    pub fn actions(&mut self) -> Mut<'_, Actions> {
+        self.app.world.resource_mut::<Actions>()
+    }
+
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        self.app.world.spawn(bundle).id()
+    }
+
+    pub fn get<C: Component>(&self, entity: Entity) -> &C {
+        self.app.world.get::<C>(entity).unwrap()
+    }
+
+    /// Every match of the query right now
+    pub fn query<D: QueryData, F: QueryFilter>(&mut self) -> Vec<ROQueryItem<'_, D>> {
+        let mut query = self.app.world.query_filtered::<D, F>();
+        query.iter(&self.app.world).collect()
+    }
+
+    /// Whether the two entities collided during the last tick
+    pub fn collided(&self, a: Entity, b: Entity) -> bool {
+        let events = self.app.world.resource::<Events<CollisionEvent>>();
+        events.get_reader().read(events).any(|event| {
+            (event.entity1 == a && event.entity2 == b) || (event.entity1 == b && event.entity2 == a)
+        })
+    }
+
+    /// Removes the black holes, for tests that don't want to be pulled around
+    /// Forces never reset on their own, so anything they already pulled is cleared as well
+    pub fn without_gravity(mut self) -> Self {
+        let sources: Vec<Entity> = self
+            .app
+            .world
+            .query_filtered::<Entity, With<GravitySource>>()
+            .iter(&self.app.world)
+            .collect();
+        for entity in sources {
+            self.app.world.despawn(entity);
+        }
+        let mut forces = self.app.world.query::<&mut Force>();
+        for mut force in forces.iter_mut(&mut self.app.world) {
+            force.0 = Vec2::ZERO;
+        }
+        self
+    }
+}
+
+fn unloaded_textures() -> TextureAssets {
+    TextureAssets {
+        bevy: Handle::default(),
+        github: Handle::default(),
+        monster1: Handle::default(),
+        black_hole: Handle::default(),
+        player: Handle::default(),
+    }
+}
@@ -0,0 +1,333 @@
+use crate::experience::Level;
+use crate::menu::{ButtonColors, MenuNavigation};
+use crate::player::{Player, PlayerStats};
+use crate::replay::{Playback, Recorder};
+use crate::run::GameRng;
+use crate::weapon::{Arsenal, WeaponId, MAX_WEAPON_LEVEL};
+use crate::GameState;
+use bevy::prelude::*;
+use rand::prelude::*;
+
+const CHOICE_COUNT: usize = 3;
+const NEW_WEAPON_WEIGHT: u32 = 3;
+const WEAPON_LEVEL_WEIGHT: u32 = 4;
+const STAT_WEIGHT: u32 = 2;
+/// How often each stat boost can be picked in a run, so stats can't grow without bound
+pub const MAX_STAT_STACKS: u32 = 5;
+
+pub struct UpgradePlugin;
+
+/// This plugin offers a choice of upgrades whenever the player levels up
+/// The cards are drawn during the State `GameState::LevelUp` and picking one resumes the run
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelUp), setup_upgrade_choices)
+            .add_systems(
+                Update,
+                pick_upgrade
+                    .after(MenuNavigation)
+                    .run_if(in_state(GameState::LevelUp)),
+            )
+            .add_systems(OnExit(GameState::LevelUp), cleanup_upgrade_choices);
+    }
+}
+
+/// What picking an upgrade does; stat effects are multipliers applied to the current value
+#[derive(Clone, Copy)]
+pub enum UpgradeEffect {
+    NewWeapon(WeaponId),
+    WeaponLevel(WeaponId),
+    MoveSpeed(f32),
+    PickupRadius(f32),
+    Damage(f32),
+    FireRate(f32),
+}
+
+pub struct StatUpgrade {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub effect: UpgradeEffect,
+}
+
+/// Stat boosts that can be offered on every level up, up to `MAX_STAT_STACKS` times each
+pub const STAT_UPGRADES: [StatUpgrade; 4] = [
+    StatUpgrade {
+        name: "Thrusters",
+        description: "+15% move speed",
+        effect: UpgradeEffect::MoveSpeed(1.15),
+    },
+    StatUpgrade {
+        name: "Magnet",
+        description: "+30% pickup radius",
+        effect: UpgradeEffect::PickupRadius(1.3),
+    },
+    StatUpgrade {
+        name: "Overcharge",
+        description: "+10% damage for all weapons",
+        effect: UpgradeEffect::Damage(1.1),
+    },
+    StatUpgrade {
+        name: "Coolant",
+        description: "-8% cooldown for all weapons",
+        effect: UpgradeEffect::FireRate(0.92),
+    },
+];
+
+/// How often each of the [`STAT_UPGRADES`] was picked this run
+#[derive(Component, Default)]
+pub struct StatStacks([u32; STAT_UPGRADES.len()]);
+
+#[derive(Clone)]
+struct UpgradeCard {
+    title: String,
+    description: String,
+    effect: UpgradeEffect,
+    /// Index into `STAT_UPGRADES` for stat boosts
+    stat: Option<usize>,
+    weight: u32,
+}
+
+/// The cards offered for the current level up
+#[derive(Resource)]
+struct UpgradeChoices(Vec<UpgradeCard>);
+
+#[derive(Component)]
+struct UpgradeMenu;
+
+#[derive(Component)]
+struct UpgradeButton(usize);
+
+/// Every upgrade the player could get right now: new weapons they don't own,
+/// levels for weapons that aren't maxed out yet and stat boosts that weren't picked too often yet
+fn available_upgrades(arsenal: &Arsenal, stacks: &StatStacks) -> Vec<UpgradeCard> {
+    let weapon_cards = WeaponId::ALL.iter().filter_map(|id| {
+        let name = id.definition().name;
+        match arsenal.get(*id) {
+            None => Some(UpgradeCard {
+                title: name.to_string(),
+                description: "New weapon".to_string(),
+                effect: UpgradeEffect::NewWeapon(*id),
+                stat: None,
+                weight: NEW_WEAPON_WEIGHT,
+            }),
+            Some(weapon) if weapon.level < MAX_WEAPON_LEVEL => Some(UpgradeCard {
+                title: name.to_string(),
+                description: format!("Level {}", weapon.level + 1),
+                effect: UpgradeEffect::WeaponLevel(*id),
+                stat: None,
+                weight: WEAPON_LEVEL_WEIGHT,
+            }),
+            Some(_) => None,
+        }
+    });
+    let stat_cards = STAT_UPGRADES
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| stacks.0[*index] < MAX_STAT_STACKS)
+        .map(|(index, upgrade)| UpgradeCard {
+            title: upgrade.name.to_string(),
+            description: upgrade.description.to_string(),
+            effect: upgrade.effect,
+            stat: Some(index),
+            weight: STAT_WEIGHT,
+        });
+
+    weapon_cards.chain(stat_cards).collect()
+}
+
+fn apply_upgrade(effect: UpgradeEffect, arsenal: &mut Arsenal, stats: &mut PlayerStats) {
+    match effect {
+        UpgradeEffect::NewWeapon(id) => arsenal.add(id),
+        UpgradeEffect::WeaponLevel(id) => {
+            if let Some(weapon) = arsenal.get_mut(id) {
+                weapon.level = (weapon.level + 1).min(MAX_WEAPON_LEVEL);
+            }
+        }
+        UpgradeEffect::MoveSpeed(factor) => stats.move_speed *= factor,
+        UpgradeEffect::PickupRadius(factor) => stats.pickup_radius *= factor,
+        UpgradeEffect::Damage(factor) => stats.damage_multiplier *= factor,
+        UpgradeEffect::FireRate(factor) => stats.cooldown_multiplier *= factor,
+    }
+}
+
+fn setup_upgrade_choices(
+    mut commands: Commands,
+    player_query: Query<(&Arsenal, &StatStacks, &Level), With<Player>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((arsenal, stacks, level)) = player_query.get_single() else {
+        return;
+    };
+
+    let choices: Vec<UpgradeCard> = available_upgrades(arsenal, stacks)
+        .choose_multiple_weighted(&mut **rng, CHOICE_COUNT, |card| card.weight)
+        .map(|chosen| chosen.cloned().collect())
+        .unwrap_or_default();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            UpgradeMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                format!("Level {}", level.level),
+                TextStyle {
+                    font_size: 50.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (index, card) in choices.iter().enumerate() {
+                        spawn_upgrade_button(row, index, card);
+                    }
+                });
+        });
+
+    commands.insert_resource(UpgradeChoices(choices));
+}
+
+fn spawn_upgrade_button(parent: &mut ChildBuilder, index: usize, card: &UpgradeCard) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(200.0),
+                    height: Val::Px(140.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(10.)),
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            UpgradeButton(index),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("{}. {}", index + 1, card.title),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                card.description.clone(),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.7, 0.7, 0.7),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn pick_upgrade(
+    mut next_state: ResMut<NextState<GameState>>,
+    choices: Option<Res<UpgradeChoices>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            &UpgradeButton,
+        ),
+        Changed<Interaction>,
+    >,
+    mut player_query: Query<(&mut Arsenal, &mut PlayerStats, &mut StatStacks), With<Player>>,
+    playback: Option<ResMut<Playback>>,
+    recorder: Option<ResMut<Recorder>>,
+) {
+    // nothing to pick from, e.g. when the player was gone before the cards could be drawn
+    let Some(choices) = choices.filter(|choices| !choices.0.is_empty()) else {
+        next_state.set(GameState::Playing);
+        return;
+    };
+
+    let mut picked = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3]
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for (interaction, mut color, button_colors, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => picked = Some(button.0),
+            Interaction::Hovered => *color = button_colors.hovered.into(),
+            Interaction::None => *color = button_colors.normal.into(),
+        }
+    }
+    // replays pick what was picked in the recorded run
+    if let Some(mut playback) = playback {
+        picked = playback.next_pick();
+    }
+
+    let Some((index, card)) =
+        picked.and_then(|index| choices.0.get(index).map(|card| (index, card)))
+    else {
+        return;
+    };
+    if let Some(mut recorder) = recorder {
+        recorder.record_pick(index);
+    }
+    if let Ok((mut arsenal, mut stats, mut stacks)) = player_query.get_single_mut() {
+        apply_upgrade(card.effect, &mut arsenal, &mut stats);
+        if let Some(stat) = card.stat {
+            stacks.0[stat] += 1;
+        }
+    }
+    next_state.set(GameState::Playing);
+}
+
+fn cleanup_upgrade_choices(mut commands: Commands, menu: Query<Entity, With<UpgradeMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<UpgradeChoices>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weapon::STARTING_WEAPONS;
+
+    #[test]
+    fn maxed_out_stat_boosts_are_no_longer_offered() {
+        let arsenal = Arsenal::new(&STARTING_WEAPONS);
+        let mut stacks = StatStacks::default();
+        stacks.0[0] = MAX_STAT_STACKS;
+        stacks.0[1] = MAX_STAT_STACKS - 1;
+
+        let offered: Vec<Option<usize>> = available_upgrades(&arsenal, &stacks)
+            .iter()
+            .map(|card| card.stat)
+            .filter(Option::is_some)
+            .collect();
+        assert_eq!(offered, vec![Some(1), Some(2), Some(3)]);
+    }
+}
@@ -0,0 +1,74 @@
+use crate::run::RunSetup;
+use crate::{GameState, GameplaySet};
+use bevy::prelude::*;
+use std::time::Duration;
+
+const WAVE_DURATION: Duration = Duration::from_secs(30);
+const FIRST_SPAWN_INTERVAL: f32 = 3.0;
+const MIN_SPAWN_INTERVAL: f32 = 0.25;
+/// Each wave spawns enemies this much more often than the one before
+const SPAWN_INTERVAL_FALLOFF: f32 = 0.85;
+
+pub struct WavePlugin;
+
+/// This plugin counts waves during a run, each one spawning enemies faster than the last
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WaveCompleted>()
+            .add_systems(OnEnter(GameState::Playing), start_waves.in_set(RunSetup))
+            .add_systems(
+                FixedUpdate,
+                advance_wave
+                    .in_set(GameplaySet::EnemyUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Wave {
+    pub number: u32,
+    /// Time until the next wave starts
+    pub timer: Timer,
+}
+
+/// Sent when the player survived a wave and the next one starts
+#[derive(Event)]
+pub struct WaveCompleted {
+    pub number: u32,
+}
+
+impl Wave {
+    /// Time between enemy spawns
+    pub fn spawn_interval(&self) -> Duration {
+        let interval = FIRST_SPAWN_INTERVAL * SPAWN_INTERVAL_FALLOFF.powi(self.number as i32 - 1);
+        Duration::from_secs_f32(interval.max(MIN_SPAWN_INTERVAL))
+    }
+
+    /// Enemies spawned at once
+    pub fn spawn_count(&self) -> u32 {
+        1 + self.number / 3
+    }
+}
+
+fn start_waves(mut commands: Commands) {
+    commands.insert_resource(Wave {
+        number: 1,
+        timer: Timer::new(WAVE_DURATION, TimerMode::Repeating),
+    });
+}
+
+fn advance_wave(
+    time: Res<Time>,
+    mut wave: ResMut<Wave>,
+    mut wave_completed_events: EventWriter<WaveCompleted>,
+) {
+    wave.timer.tick(time.delta());
+    if wave.timer.just_finished() {
+        wave_completed_events.send(WaveCompleted {
+            number: wave.number,
+        });
+        wave.number += 1;
+        info!("wave {}", wave.number);
+    }
+}
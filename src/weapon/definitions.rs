@@ -0,0 +1,217 @@
+use crate::projectile::ProjectileBehaviour;
+use std::time::Duration;
+
+/// Every weapon the player can own; the definition behind each one lives in [`WeaponId::definition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WeaponId {
+    Blaster,
+    Shotgun,
+    Beam,
+    Missiles,
+    Glaive,
+    ArcCaster,
+    Blades,
+    Pulse,
+    Mines,
+}
+
+pub struct WeaponDefinition {
+    pub name: &'static str,
+    pub kind: WeaponKind,
+    pub cooldown: Duration,
+    pub damage: f32,
+    pub trigger: Trigger,
+}
+
+impl WeaponDefinition {
+    pub fn auto_fire(&self) -> bool {
+        self.trigger == Trigger::Auto
+    }
+}
+
+/// How the attack input fires a weapon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Fires on its own whenever the cooldown is up, the attack input isn't needed
+    Auto,
+    /// Keeps firing while the attack input is held
+    FullAuto,
+    /// Fires once per press of the attack input
+    SemiAuto,
+    /// Fires when the attack input is released, doing more damage the longer it was held up to `full`
+    Charge { full: Duration },
+}
+
+pub enum WeaponKind {
+    /// Fires `count` projectiles fanned out over `spread` radians, each with all of `behaviours`
+    Projectile {
+        count: u32,
+        spread: f32,
+        speed: f32,
+        behaviours: &'static [ProjectileBehaviour],
+    },
+    /// Blades circling the player, each one hits whatever it touches once per cooldown
+    Orbit {
+        count: u32,
+        radius: f32,
+        angular_speed: f32,
+    },
+    /// A wave pushed out from the player that damages everything within `radius`
+    Pulse { radius: f32 },
+    /// Dropped at the player's feet, explodes once an enemy comes close after arming
+    Mine { radius: f32, arm_time: Duration },
+}
+
+impl WeaponId {
+    pub const ALL: [WeaponId; 9] = [
+        WeaponId::Blaster,
+        WeaponId::Shotgun,
+        WeaponId::Beam,
+        WeaponId::Missiles,
+        WeaponId::Glaive,
+        WeaponId::ArcCaster,
+        WeaponId::Blades,
+        WeaponId::Pulse,
+        WeaponId::Mines,
+    ];
+
+    pub fn definition(self) -> &'static WeaponDefinition {
+        match self {
+            WeaponId::Blaster => &BLASTER,
+            WeaponId::Shotgun => &SHOTGUN,
+            WeaponId::Beam => &BEAM,
+            WeaponId::Missiles => &MISSILES,
+            WeaponId::Glaive => &GLAIVE,
+            WeaponId::ArcCaster => &ARC_CASTER,
+            WeaponId::Blades => &BLADES,
+            WeaponId::Pulse => &PULSE,
+            WeaponId::Mines => &MINES,
+        }
+    }
+}
+
+const BLASTER: WeaponDefinition = WeaponDefinition {
+    name: "Blaster",
+    kind: WeaponKind::Projectile {
+        count: 1,
+        spread: 0.0,
+        speed: 300.0,
+        behaviours: &[],
+    },
+    cooldown: Duration::from_millis(500),
+    damage: 50.0,
+    trigger: Trigger::FullAuto,
+};
+
+const SHOTGUN: WeaponDefinition = WeaponDefinition {
+    name: "Shotgun",
+    kind: WeaponKind::Projectile {
+        count: 5,
+        spread: 0.6,
+        speed: 450.0,
+        behaviours: &[ProjectileBehaviour::Ricochet(2)],
+    },
+    cooldown: Duration::from_millis(900),
+    damage: 20.0,
+    trigger: Trigger::SemiAuto,
+};
+
+const BEAM: WeaponDefinition = WeaponDefinition {
+    name: "Beam",
+    kind: WeaponKind::Projectile {
+        count: 1,
+        spread: 0.0,
+        speed: 1200.0,
+        behaviours: &[ProjectileBehaviour::Pierce(10)],
+    },
+    cooldown: Duration::from_millis(150),
+    damage: 12.0,
+    trigger: Trigger::FullAuto,
+};
+
+const MISSILES: WeaponDefinition = WeaponDefinition {
+    name: "Homing Missiles",
+    kind: WeaponKind::Projectile {
+        count: 2,
+        spread: 0.8,
+        speed: 250.0,
+        behaviours: &[
+            ProjectileBehaviour::Homing {
+                turn_rate: 4.0,
+                range: 800.0,
+            },
+            ProjectileBehaviour::Split {
+                count: 3,
+                spread: 1.2,
+            },
+        ],
+    },
+    cooldown: Duration::from_millis(1200),
+    damage: 60.0,
+    trigger: Trigger::Auto,
+};
+
+const GLAIVE: WeaponDefinition = WeaponDefinition {
+    name: "Glaive",
+    kind: WeaponKind::Projectile {
+        count: 1,
+        spread: 0.0,
+        speed: 500.0,
+        behaviours: &[
+            ProjectileBehaviour::Boomerang { distance: 450.0 },
+            ProjectileBehaviour::Pierce(8),
+        ],
+    },
+    cooldown: Duration::from_millis(1000),
+    damage: 35.0,
+    trigger: Trigger::Charge {
+        full: Duration::from_millis(1200),
+    },
+};
+
+const ARC_CASTER: WeaponDefinition = WeaponDefinition {
+    name: "Arc Caster",
+    kind: WeaponKind::Projectile {
+        count: 1,
+        spread: 0.0,
+        speed: 700.0,
+        behaviours: &[ProjectileBehaviour::Chain {
+            jumps: 4,
+            range: 300.0,
+        }],
+    },
+    cooldown: Duration::from_millis(700),
+    damage: 40.0,
+    trigger: Trigger::SemiAuto,
+};
+
+const BLADES: WeaponDefinition = WeaponDefinition {
+    name: "Orbiting Blades",
+    kind: WeaponKind::Orbit {
+        count: 3,
+        radius: 150.0,
+        angular_speed: 3.0,
+    },
+    cooldown: Duration::from_millis(400),
+    damage: 20.0,
+    trigger: Trigger::Auto,
+};
+
+const PULSE: WeaponDefinition = WeaponDefinition {
+    name: "Wave Pulse",
+    kind: WeaponKind::Pulse { radius: 350.0 },
+    cooldown: Duration::from_secs(3),
+    damage: 40.0,
+    trigger: Trigger::Auto,
+};
+
+const MINES: WeaponDefinition = WeaponDefinition {
+    name: "Mines",
+    kind: WeaponKind::Mine {
+        radius: 120.0,
+        arm_time: Duration::from_millis(500),
+    },
+    cooldown: Duration::from_secs(2),
+    damage: 80.0,
+    trigger: Trigger::Auto,
+};
@@ -0,0 +1,630 @@
+use crate::actions::{Actions, AimSource};
+use crate::audio::{Sfx, SoundEvent};
+use crate::camera::CameraImpulse;
+use crate::collision::{Collider, CollisionEvent, CollisionLayer};
+use crate::combat::DamageEvent;
+use crate::enemy::Enemy;
+use crate::interpolation::Interpolated;
+use crate::pickup::PowerUps;
+use crate::player::{Player, PlayerStats};
+use crate::projectile::{fan_directions, nearest_enemy, ProjectileSpawner};
+use crate::run::{GameRng, GameplayEntity};
+use crate::{GameState, GameplaySet, ZLayer};
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+pub use crate::weapon::definitions::{Trigger, WeaponId, WeaponKind};
+
+mod definitions;
+
+/// Auto-firing projectile weapons only pick targets this close to the player
+pub const AUTO_AIM_RANGE: f32 = 800.0;
+pub const STARTING_WEAPONS: [WeaponId; 1] = [WeaponId::Blaster];
+pub const MAX_WEAPON_LEVEL: u32 = 5;
+
+const BLADE_SIZE: Vec2 = Vec2::new(40.0, 12.0);
+const MINE_RADIUS: f32 = 16.0;
+const MINE_TRIGGER_DISTANCE: f32 = 60.0;
+const MINE_TRAUMA: f32 = 0.25;
+const MINE_LIFETIME: Duration = Duration::from_secs(20);
+const PULSE_WAVE_DURATION: Duration = Duration::from_millis(400);
+/// Damage of a charged attack released right away, as a fraction of its fully charged damage
+const MIN_CHARGE_DAMAGE: f32 = 0.3;
+
+pub struct WeaponPlugin;
+
+/// This plugin fires every weapon in the player's [`Arsenal`]
+/// Manual weapons fire where the player aims according to their [`Trigger`], auto weapons fire on their own
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_weapon_assets)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (switch_weapon, fire_weapons)
+                        .chain()
+                        .in_set(GameplaySet::PlayerUpdate),
+                    (sync_orbit_blades, spin_orbit_blades)
+                        .chain()
+                        .in_set(GameplaySet::PlayerUpdate)
+                        .after(fire_weapons),
+                    (detonate_mines, blade_hits).in_set(GameplaySet::Collisions),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                draw_pulse_waves.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// All weapons carried by the player
+/// `active` is the manual weapon that fires on attack, switched with the number keys or scroll wheel
+#[derive(Component)]
+pub struct Arsenal {
+    pub weapons: Vec<Weapon>,
+    pub active: usize,
+}
+
+pub struct Weapon {
+    pub id: WeaponId,
+    pub level: u32,
+    pub timer: Timer,
+    /// Charge of a charged attack released during the cooldown, it fires as soon as the weapon is ready
+    pub queued_charge: Option<f32>,
+}
+
+impl Weapon {
+    pub fn new(id: WeaponId) -> Self {
+        Self {
+            id,
+            level: 1,
+            timer: Timer::new(id.definition().cooldown, TimerMode::Once),
+            queued_charge: None,
+        }
+    }
+
+    /// Each level past the first adds a quarter of the base damage
+    pub fn damage(&self, stats: &PlayerStats) -> f32 {
+        let level_bonus = 1.0 + 0.25 * (self.level - 1) as f32;
+        self.id.definition().damage * level_bonus * stats.damage_multiplier
+    }
+
+    /// Each level past the first shaves 8% off the base cooldown
+    pub fn cooldown(&self, stats: &PlayerStats) -> Duration {
+        let level_bonus = 1.0 - 0.08 * (self.level - 1) as f32;
+        self.id
+            .definition()
+            .cooldown
+            .mul_f32(level_bonus * stats.cooldown_multiplier)
+    }
+}
+
+impl Arsenal {
+    pub fn get(&self, id: WeaponId) -> Option<&Weapon> {
+        self.weapons.iter().find(|weapon| weapon.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: WeaponId) -> Option<&mut Weapon> {
+        self.weapons.iter_mut().find(|weapon| weapon.id == id)
+    }
+
+    pub fn add(&mut self, id: WeaponId) {
+        if self.get(id).is_none() {
+            self.weapons.push(Weapon::new(id));
+        }
+    }
+
+    pub fn new(weapons: &[WeaponId]) -> Self {
+        let weapons: Vec<Weapon> = weapons.iter().copied().map(Weapon::new).collect();
+        let active = weapons
+            .iter()
+            .position(|weapon| !weapon.id.definition().auto_fire())
+            .unwrap_or(0);
+        Self { weapons, active }
+    }
+
+    fn manual_slots(&self) -> Vec<usize> {
+        self.weapons
+            .iter()
+            .enumerate()
+            .filter(|(_, weapon)| !weapon.id.definition().auto_fire())
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    /// Selects the `index`th manual weapon, if there is one
+    pub fn select(&mut self, index: usize) {
+        if let Some(slot) = self.manual_slots().get(index) {
+            self.active = *slot;
+        }
+    }
+
+    /// Moves `step` manual weapons forward (or back if negative), wrapping around
+    pub fn cycle(&mut self, step: i32) {
+        let slots = self.manual_slots();
+        if slots.is_empty() {
+            return;
+        }
+        let current = slots
+            .iter()
+            .position(|slot| *slot == self.active)
+            .unwrap_or(0);
+        let next = (current as i32 + step).rem_euclid(slots.len() as i32);
+        self.active = slots[next as usize];
+    }
+}
+
+#[derive(Resource)]
+struct WeaponAssets {
+    blade_mesh: Mesh2dHandle,
+    blade_material: Handle<ColorMaterial>,
+    mine_mesh: Mesh2dHandle,
+    mine_material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+struct OrbitBlade {
+    weapon: WeaponId,
+    angle: f32,
+    /// Enemies hit recently and the seconds until this blade may hit them again
+    recent_hits: Vec<(Entity, f32)>,
+}
+
+#[derive(Component)]
+struct Mine {
+    damage: f32,
+    radius: f32,
+    arm: Timer,
+    life: Timer,
+}
+
+#[derive(Component)]
+struct PulseWave {
+    radius: f32,
+    timer: Timer,
+}
+
+fn setup_weapon_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(WeaponAssets {
+        blade_mesh: Mesh2dHandle(meshes.add(Rectangle::new(BLADE_SIZE.x, BLADE_SIZE.y))),
+        blade_material: materials.add(Color::rgb(0.85, 0.85, 0.95)),
+        mine_mesh: Mesh2dHandle(meshes.add(Circle::new(MINE_RADIUS))),
+        mine_material: materials.add(Color::rgb(0.9, 0.3, 0.2)),
+    });
+}
+
+/// Uses up the weapon picks that piled up since the last tick
+fn switch_weapon(
+    mut actions: ResMut<Actions>,
+    mut arsenal_query: Query<&mut Arsenal, With<Player>>,
+) {
+    let slot = actions.weapon_slot.take();
+    let cycle = std::mem::take(&mut actions.weapon_cycle);
+    let Ok(mut arsenal) = arsenal_query.get_single_mut() else {
+        return;
+    };
+
+    let previous = arsenal.active;
+    if let Some(index) = slot {
+        arsenal.select(index);
+    }
+    if cycle != 0 {
+        arsenal.cycle(cycle);
+    }
+
+    if arsenal.active != previous {
+        info!(
+            "switched to {}",
+            arsenal.weapons[arsenal.active].id.definition().name
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_weapons(
+    mut projectiles: ProjectileSpawner,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut sounds: EventWriter<SoundEvent>,
+    actions: Res<Actions>,
+    time: Res<Time>,
+    weapon_assets: Res<WeaponAssets>,
+    mut player_query: Query<(&Transform, &mut Arsenal, &PlayerStats, &PowerUps), With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    enemy_entity_query: Query<(Entity, &Transform), With<Enemy>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((player_transform, mut arsenal, stats, power_ups)) = player_query.get_single_mut()
+    else {
+        return;
+    };
+    let origin = player_transform.translation;
+    let active = arsenal.active;
+
+    for (slot, weapon) in arsenal.weapons.iter_mut().enumerate() {
+        let cooldown = weapon.cooldown(stats);
+        weapon.timer.set_duration(cooldown);
+        weapon
+            .timer
+            .tick(time.delta().mul_f32(power_ups.fire_rate_multiplier()));
+        let definition = weapon.id.definition();
+        let mut damage = weapon.damage(stats);
+        let fire = &actions.fire;
+        // auto aim holds fire down, so it can't press or release it
+        let auto_aimed = actions.aim.source == AimSource::Auto;
+        if slot != active {
+            weapon.queued_charge = None;
+        }
+        let triggered = match definition.trigger {
+            Trigger::Auto => true,
+            _ if slot != active => false,
+            Trigger::FullAuto => fire.pressed,
+            Trigger::SemiAuto => fire.just_pressed || (auto_aimed && fire.pressed),
+            Trigger::Charge { full } => {
+                let charge = (fire.held.as_secs_f32() / full.as_secs_f32()).min(1.0);
+                if fire.just_released || (auto_aimed && fire.pressed && charge >= 1.0) {
+                    weapon.queued_charge = Some(charge);
+                }
+                match weapon.queued_charge {
+                    Some(charge) if weapon.timer.finished() => {
+                        weapon.queued_charge = None;
+                        damage *= MIN_CHARGE_DAMAGE + (1.0 - MIN_CHARGE_DAMAGE) * charge;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        };
+        if !weapon.timer.finished() || !triggered {
+            continue;
+        }
+
+        let fired = match definition.kind {
+            WeaponKind::Projectile {
+                count,
+                spread,
+                speed,
+                behaviours,
+            } => {
+                let aim = if definition.auto_fire() {
+                    nearest_enemy(origin.truncate(), AUTO_AIM_RANGE, &enemy_query)
+                        .map(|target| (target - origin.truncate()).normalize_or_zero())
+                } else {
+                    actions.aim.direction
+                };
+                let Some(aim) = aim else {
+                    continue;
+                };
+
+                for direction in fan_directions(aim, count, spread) {
+                    let entity = projectiles.spawn(origin, direction * speed, damage);
+                    for behaviour in behaviours {
+                        projectiles.add_behaviour(entity, *behaviour);
+                    }
+                }
+                true
+            }
+            // blades are always out, see `spin_orbit_blades`
+            WeaponKind::Orbit { .. } => false,
+            WeaponKind::Pulse { radius } => {
+                for (enemy, enemy_transform) in enemy_entity_query.iter() {
+                    if enemy_transform
+                        .translation
+                        .truncate()
+                        .distance(origin.truncate())
+                        <= radius
+                    {
+                        let (amount, critical) = stats.roll_damage(damage, &mut **rng);
+                        damage_events.send(DamageEvent {
+                            target: enemy,
+                            amount,
+                            critical,
+                        });
+                    }
+                }
+                projectiles.commands().spawn((
+                    SpatialBundle::from_transform(Transform::from_translation(origin)),
+                    PulseWave {
+                        radius,
+                        timer: Timer::new(PULSE_WAVE_DURATION, TimerMode::Once),
+                    },
+                    GameplayEntity,
+                ));
+                true
+            }
+            WeaponKind::Mine { radius, arm_time } => {
+                projectiles.commands().spawn((
+                    MaterialMesh2dBundle {
+                        mesh: weapon_assets.mine_mesh.clone(),
+                        material: weapon_assets.mine_material.clone(),
+                        transform: Transform::from_translation(
+                            origin.truncate().extend(ZLayer::Game.into()),
+                        ),
+                        ..default()
+                    },
+                    Mine {
+                        damage,
+                        radius,
+                        arm: Timer::new(arm_time, TimerMode::Once),
+                        life: Timer::new(MINE_LIFETIME, TimerMode::Once),
+                    },
+                    GameplayEntity,
+                ));
+                true
+            }
+        };
+
+        if fired {
+            weapon.timer.reset();
+            sounds.send(SoundEvent::at(Sfx::Shot, origin.truncate()));
+        }
+    }
+}
+
+/// Keeps one blade entity per blade of every orbit weapon in the arsenal
+fn sync_orbit_blades(
+    mut commands: Commands,
+    weapon_assets: Res<WeaponAssets>,
+    arsenal_query: Query<&Arsenal, With<Player>>,
+    blade_query: Query<(Entity, &OrbitBlade)>,
+) {
+    let Ok(arsenal) = arsenal_query.get_single() else {
+        return;
+    };
+
+    for id in WeaponId::ALL {
+        let expected = match id.definition().kind {
+            WeaponKind::Orbit { count, .. } if arsenal.get(id).is_some() => count,
+            _ => 0,
+        };
+        let blades: Vec<Entity> = blade_query
+            .iter()
+            .filter(|(_, blade)| blade.weapon == id)
+            .map(|(entity, _)| entity)
+            .collect();
+        if blades.len() == expected as usize {
+            continue;
+        }
+
+        for entity in blades {
+            commands.entity(entity).despawn();
+        }
+        for index in 0..expected {
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: weapon_assets.blade_mesh.clone(),
+                    material: weapon_assets.blade_material.clone(),
+                    transform: Transform::from_xyz(0., 0., ZLayer::Character.into()),
+                    ..default()
+                },
+                OrbitBlade {
+                    weapon: id,
+                    angle: TAU * index as f32 / expected as f32,
+                    recent_hits: Vec::new(),
+                },
+                Collider::new_aabb(CollisionLayer::PlayerProjectile, BLADE_SIZE / 2.0),
+                Interpolated::default(),
+                GameplayEntity,
+            ));
+        }
+    }
+}
+
+fn spin_orbit_blades(
+    time: Res<Time>,
+    player_query: Query<&Transform, (With<Player>, Without<OrbitBlade>)>,
+    mut blade_query: Query<(&mut Transform, &mut OrbitBlade)>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut transform, mut blade) in blade_query.iter_mut() {
+        let WeaponKind::Orbit {
+            radius,
+            angular_speed,
+            ..
+        } = blade.weapon.definition().kind
+        else {
+            continue;
+        };
+
+        blade.angle = (blade.angle + angular_speed * time.delta_seconds()).rem_euclid(TAU);
+        let offset = Vec2::from_angle(blade.angle) * radius;
+        transform.translation.x = player_transform.translation.x + offset.x;
+        transform.translation.y = player_transform.translation.y + offset.y;
+        transform.rotation = Quat::from_rotation_z(blade.angle);
+
+        let delta = time.delta_seconds();
+        blade.recent_hits.retain_mut(|(_, cooldown)| {
+            *cooldown -= delta;
+            *cooldown > 0.0
+        });
+    }
+}
+
+fn blade_hits(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut blade_query: Query<&mut OrbitBlade>,
+    player_query: Query<(&Arsenal, &PlayerStats), With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((arsenal, stats)) = player_query.get_single() else {
+        return;
+    };
+
+    for event in collision_events.read() {
+        let (blade_entity, target) = if blade_query.contains(event.entity1) {
+            (event.entity1, event.entity2)
+        } else if blade_query.contains(event.entity2) {
+            (event.entity2, event.entity1)
+        } else {
+            continue;
+        };
+        if !enemy_query.contains(target) {
+            continue;
+        }
+
+        let mut blade = blade_query.get_mut(blade_entity).unwrap();
+        if blade.recent_hits.iter().any(|(enemy, _)| *enemy == target) {
+            continue;
+        }
+        let Some(weapon) = arsenal.get(blade.weapon) else {
+            continue;
+        };
+        let (amount, critical) = stats.roll_damage(weapon.damage(stats), &mut **rng);
+        damage_events.send(DamageEvent {
+            target,
+            amount,
+            critical,
+        });
+        blade
+            .recent_hits
+            .push((target, weapon.cooldown(stats).as_secs_f32()));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detonate_mines(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut impulses: EventWriter<CameraImpulse>,
+    mut mine_query: Query<(Entity, &Transform, &mut Mine)>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    player_query: Query<&PlayerStats, With<Player>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok(stats) = player_query.get_single() else {
+        return;
+    };
+    for (entity, transform, mut mine) in mine_query.iter_mut() {
+        mine.arm.tick(time.delta());
+        mine.life.tick(time.delta());
+        if mine.life.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if !mine.arm.finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let triggered = enemy_query.iter().any(|(_, enemy_transform)| {
+            enemy_transform.translation.truncate().distance(position) <= MINE_TRIGGER_DISTANCE
+        });
+        if !triggered {
+            continue;
+        }
+
+        for (enemy, enemy_transform) in enemy_query.iter() {
+            if enemy_transform.translation.truncate().distance(position) <= mine.radius {
+                let (amount, critical) = stats.roll_damage(mine.damage, &mut **rng);
+                damage_events.send(DamageEvent {
+                    target: enemy,
+                    amount,
+                    critical,
+                });
+            }
+        }
+        impulses.send(CameraImpulse::shake(MINE_TRAUMA));
+        commands.entity(entity).despawn();
+        commands.spawn((
+            SpatialBundle::from_transform(*transform),
+            PulseWave {
+                radius: mine.radius,
+                timer: Timer::new(PULSE_WAVE_DURATION, TimerMode::Once),
+            },
+            GameplayEntity,
+        ));
+    }
+}
+
+fn draw_pulse_waves(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    mut wave_query: Query<(Entity, &Transform, &mut PulseWave)>,
+) {
+    for (entity, transform, mut wave) in wave_query.iter_mut() {
+        wave.timer.tick(time.delta());
+        if wave.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = wave.timer.fraction();
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            wave.radius * progress,
+            Color::rgba(0.4, 0.8, 1.0, 1.0 - progress),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Manual weapons in slots 1, 3 and 4, auto weapons in between
+    fn mixed_arsenal() -> Arsenal {
+        Arsenal::new(&[
+            WeaponId::Missiles,
+            WeaponId::Blaster,
+            WeaponId::Blades,
+            WeaponId::Shotgun,
+            WeaponId::Beam,
+        ])
+    }
+
+    #[test]
+    fn starts_on_the_first_manual_weapon() {
+        assert_eq!(mixed_arsenal().active, 1);
+        assert_eq!(Arsenal::new(&[WeaponId::Missiles]).active, 0);
+    }
+
+    #[test]
+    fn cycling_skips_auto_weapons_and_wraps_both_ways() {
+        let mut arsenal = mixed_arsenal();
+        arsenal.cycle(1);
+        assert_eq!(arsenal.active, 3);
+        arsenal.cycle(1);
+        assert_eq!(arsenal.active, 4);
+        arsenal.cycle(1);
+        assert_eq!(arsenal.active, 1);
+        arsenal.cycle(-1);
+        assert_eq!(arsenal.active, 4);
+        arsenal.cycle(-2);
+        assert_eq!(arsenal.active, 1);
+    }
+
+    #[test]
+    fn selecting_counts_only_manual_weapons() {
+        let mut arsenal = mixed_arsenal();
+        arsenal.select(2);
+        assert_eq!(arsenal.active, 4);
+        arsenal.select(3);
+        assert_eq!(arsenal.active, 4);
+    }
+
+    #[test]
+    fn max_level_doubles_damage_and_shortens_cooldown() {
+        let stats = PlayerStats::default();
+        let mut weapon = Weapon::new(WeaponId::Blaster);
+        let definition = WeaponId::Blaster.definition();
+        assert_eq!(weapon.damage(&stats), definition.damage);
+        assert_eq!(weapon.cooldown(&stats), definition.cooldown);
+
+        weapon.level = MAX_WEAPON_LEVEL;
+        assert_eq!(weapon.damage(&stats), definition.damage * 2.0);
+        assert_eq!(weapon.cooldown(&stats), definition.cooldown.mul_f32(0.68));
+    }
+}